@@ -19,10 +19,10 @@ mod app {
 
     #[local]
     struct Local {
-        a: u32,
-        b: u32,
-        c: u32,
-        d: u32,
+        e: u32,
+        f: u32,
+        g: u32,
+        h: u32,
     }
 
     #[init]
@@ -1,41 +1,108 @@
 use syn::Ident;
 
 use crate::{
-    analyze::Priority,
+    analyze::{Priority, Task, TaskKind, TaskResources},
     ast::{Access, App, Local, TaskLocal},
 };
 
 impl App {
+    /// Get all tasks, except `init`, along with their kind and priority
+    pub fn tasks_with_kind(&self) -> Vec<(Task, TaskKind, Priority)> {
+        self.idle
+            .iter()
+            .map(|idle| (idle.name.clone(), TaskKind::Idle, 0))
+            .chain(
+                self.software_tasks
+                    .iter()
+                    .map(|(name, task)| (name.clone(), TaskKind::Software, task.args.priority)),
+            )
+            .chain(
+                self.hardware_tasks
+                    .iter()
+                    .map(|(name, task)| (name.clone(), TaskKind::Hardware, task.args.priority)),
+            )
+            .collect()
+    }
+
+    /// Get `init`, `idle` and all hardware and software tasks along with the resources they access
+    pub fn task_resources(&self) -> Vec<TaskResources> {
+        Some(TaskResources {
+            name: self.init.name.clone(),
+            shared: vec![],
+            local: self.init.args.local_resources.keys().cloned().collect(),
+            priority: 0,
+        })
+        .into_iter()
+        .chain(self.idle.iter().map(|idle| TaskResources {
+            name: idle.name.clone(),
+            shared: idle.args.shared_resources.keys().cloned().collect(),
+            local: idle.args.local_resources.keys().cloned().collect(),
+            priority: 0,
+        }))
+        .chain(
+            self.software_tasks
+                .iter()
+                .map(|(name, task)| TaskResources {
+                    name: name.clone(),
+                    shared: task.args.shared_resources.keys().cloned().collect(),
+                    local: task.args.local_resources.keys().cloned().collect(),
+                    priority: task.args.priority,
+                }),
+        )
+        .chain(
+            self.hardware_tasks
+                .iter()
+                .map(|(name, task)| TaskResources {
+                    name: name.clone(),
+                    shared: task.args.shared_resources.keys().cloned().collect(),
+                    local: task.args.local_resources.keys().cloned().collect(),
+                    priority: task.args.priority,
+                }),
+        )
+        .collect()
+    }
+
+    /// Get the shared resources that are accessed by at least one task, in declaration order
+    pub fn shared_resources_in_decl_order(&self) -> Vec<&Ident> {
+        let used = self
+            .shared_resource_accesses()
+            .map(|(_, _, name, _)| name)
+            .collect::<std::collections::HashSet<_>>();
+
+        self.shared_resources
+            .keys()
+            .filter(|name| used.contains(name))
+            .collect()
+    }
+
+    /// Yields `(task, priority, resource, access)` for every shared resource access
     pub(crate) fn shared_resource_accesses(
         &self,
-    ) -> impl Iterator<Item = (Option<Priority>, &Ident, Access)> {
+    ) -> impl Iterator<Item = (&Ident, Option<Priority>, &Ident, Access)> {
         self.idle
             .iter()
             .flat_map(|idle| {
                 idle.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(0), name, *access))
+                    .map(move |(name, access)| (&idle.name, Some(0), name, *access))
             })
-            .chain(self.hardware_tasks.values().flat_map(|task| {
+            .chain(self.hardware_tasks.iter().flat_map(|(task_name, task)| {
                 task.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(task.args.priority), name, *access))
+                    .map(move |(name, access)| (task_name, Some(task.args.priority), name, *access))
             }))
-            .chain(self.software_tasks.values().flat_map(|task| {
+            .chain(self.software_tasks.iter().flat_map(|(task_name, task)| {
                 task.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(task.args.priority), name, *access))
+                    .map(move |(name, access)| (task_name, Some(task.args.priority), name, *access))
             }))
     }
 
     fn is_external(task_local: &TaskLocal) -> bool {
-        match task_local {
-            TaskLocal::External => true,
-            _ => false,
-        }
+        matches!(task_local, TaskLocal::External)
     }
 
     pub(crate) fn local_resource_accesses(&self) -> impl Iterator<Item = &Ident> {
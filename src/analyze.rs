@@ -1,74 +1,40 @@
 //! RTIC application analysis
 
-use core::cmp;
+use core::{cmp, fmt};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use indexmap::IndexMap;
-use quote::format_ident;
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream as TokenStream2, TokenTree};
+use quote::quote;
 use syn::{Ident, Type};
 
 use crate::{
-    ast::{App, LocalResources, TaskLocal},
-    Set,
+    ast::{Access, App},
+    Set, Settings,
 };
 
-pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
-    // Collect all tasks into a vector
-    type TaskName = String;
-    type Priority = u8;
-
-    // The task list is a Tuple (Name, Shared Resources, Local Resources, Priority)
-    let task_resources_list: Vec<(TaskName, Vec<&Ident>, &LocalResources, Priority)> =
-        Some(&app.init)
-            .iter()
-            .map(|ht| ("init".to_string(), Vec::new(), &ht.args.local_resources, 0))
-            .chain(app.idle.iter().map(|ht| {
-                (
-                    "idle".to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    0,
-                )
-            }))
-            .chain(app.software_tasks.iter().map(|(name, ht)| {
-                (
-                    name.to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    ht.args.priority,
-                )
-            }))
-            .chain(app.hardware_tasks.iter().map(|(name, ht)| {
-                (
-                    name.to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    ht.args.priority,
-                )
-            }))
-            .collect();
+/// Analyzes an application that has already been parsed
+///
+/// Unlike `parse` and `parse2`, this function reports the errors by category. The errors come out
+/// in the order of the checks that found them or, with the `source-order` feature and when span
+/// positions are available, in source order
+pub fn app(app: &App, settings: &Settings) -> Result<Analysis, AnalysisError> {
+    // Collect all tasks along with the resources they access
+    let task_resources_list = app.task_resources();
 
     // Create the list of task Idents
     let tasks: Vec<_> = task_resources_list
         .iter()
-        .map(|x| format_ident!("{}", x.0))
+        .map(|task| task.name.clone())
+        .collect();
+
+    let task_priorities = task_resources_list
+        .iter()
+        .map(|task| (task.name.clone(), task.priority))
         .collect();
 
     let mut error = vec![];
     let mut lf_res_with_error = vec![];
-    let mut lf_hash = HashMap::new();
 
     // Collect lock free resources
     let lock_free: Vec<&Ident> = app
@@ -78,53 +44,108 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
         .map(|(i, _)| i)
         .collect();
 
-    // Check that lock_free resources are correct
-    for lf_res in lock_free.iter() {
-        for (task, tr, _, priority) in task_resources_list.iter() {
-            for r in tr {
-                // Get all uses of resources annotated lock_free
-                if lf_res == r {
-                    // HashMap returns the previous existing object if old.key == new.key
-                    if let Some(lf_res) = lf_hash.insert(r.to_string(), (task, r, priority)) {
-                        // Check if priority differ, if it does, append to
-                        // list of resources which will be annotated with errors
-                        if priority != lf_res.2 {
-                            lf_res_with_error.push(lf_res.1);
-                            lf_res_with_error.push(r);
-                        }
-                        // If the resource already violates lock free properties
-                        if lf_res_with_error.contains(&r) {
-                            lf_res_with_error.push(lf_res.1);
-                            lf_res_with_error.push(r);
-                        }
-                    }
-                }
+    // Group all uses of resources annotated lock_free, in declaration order of the resources
+    let mut lf_uses = lock_free
+        .iter()
+        .map(|name| (*name, vec![]))
+        .collect::<IndexMap<_, _>>();
+    for task in task_resources_list.iter() {
+        for r in &task.shared {
+            if let Some(uses) = lf_uses.get_mut(r) {
+                uses.push((r, task.priority));
             }
         }
     }
 
+    // Check that lock_free resources are correct: if the priorities of the uses differ, every use
+    // of the resource will be annotated with an error
+    for (_, uses) in lf_uses {
+        if uses.iter().any(|(_, priority)| *priority != uses[0].1) {
+            // e.g. "1, 2 and 3"
+            let mut priorities = uses
+                .iter()
+                .map(|(_, priority)| *priority)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|priority| priority.to_string())
+                .collect::<Vec<_>>();
+            let last = priorities.pop().expect("UNREACHABLE");
+            let priorities = format!("{} and {}", priorities.join(", "), last);
+
+            lf_res_with_error.extend(uses.into_iter().map(|(r, _)| (r, priorities.clone())));
+        }
+    }
+
     // Add error message in the resource struct
-    for r in lock_free {
-        if lf_res_with_error.contains(&&r) {
-            error.push(syn::Error::new(
+    for r in lock_free.iter() {
+        if let Some((_, priorities)) = lf_res_with_error.iter().find(|(res, _)| res == r) {
+            error.push(AnalysisError::LockFree(syn::Error::new(
                 r.span(),
                 format!(
-                    "Lock free shared resource {:?} is used by tasks at different priorities",
+                    "Lock free shared resource {:?} is used by tasks at different priorities: {}",
                     r.to_string(),
+                    priorities,
                 ),
-            ));
+            )));
         }
     }
 
     // Add error message for each use of the shared resource
-    for resource in lf_res_with_error.clone() {
-        error.push(syn::Error::new(
+    for (resource, priorities) in lf_res_with_error {
+        error.push(AnalysisError::LockFree(syn::Error::new(
             resource.span(),
             format!(
-                "Shared resource {:?} is declared lock free but used by tasks at different priorities",
+                "Shared resource {:?} is declared lock free but used by tasks at different priorities: {}",
                 resource.to_string(),
+                priorities,
             ),
-        ));
+        )));
+    }
+
+    // Check that resources annotated `#[single_task]` are not accessed by several tasks
+    let mut single_task_resources = BTreeSet::new();
+    for (name, res) in &app.shared_resources {
+        if !res.properties.single_task {
+            continue;
+        }
+
+        let uses = task_resources_list
+            .iter()
+            .filter_map(|task| {
+                task.shared
+                    .iter()
+                    .find(|r| *r == name)
+                    .map(|r| (r, &task.name))
+            })
+            .collect::<Vec<_>>();
+
+        match &uses[..] {
+            [_] => {
+                single_task_resources.insert(name.clone());
+            }
+
+            // reported, once, by the dead resource check below
+            [] => {}
+
+            _ => {
+                let tasks = uses
+                    .iter()
+                    .map(|(_, task)| task.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                for (r, _) in &uses {
+                    error.push(AnalysisError::SingleTask(syn::Error::new(
+                        r.span(),
+                        format!(
+                            "Shared resource {:?} is declared single task but used by several tasks: {}",
+                            name.to_string(),
+                            tasks,
+                        ),
+                    )));
+                }
+            }
+        }
     }
 
     // Collect local resources
@@ -132,26 +153,48 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
 
     let mut lr_with_error = vec![];
     let mut lr_hash = HashMap::new();
+    let declared_locals = app
+        .declared_local_resources()
+        .into_iter()
+        .map(|(task, name, _)| (task, name))
+        .collect::<Vec<_>>();
 
     // Check that local resources are not shared
     for lr in local {
-        for (task, _, local_resources, _) in task_resources_list.iter() {
-            for (name, res) in local_resources.iter() {
+        for task in task_resources_list.iter() {
+            for name in task.local.iter() {
                 // Get all uses of resources annotated lock_free
                 if lr == name {
-                    match res {
-                        TaskLocal::External => {
-                            // HashMap returns the previous existing object if old.key == new.key
-                            if let Some(lr) = lr_hash.insert(name.to_string(), (task, name)) {
-                                lr_with_error.push(lr.1);
-                                lr_with_error.push(name);
-                            }
-                        }
+                    if declared_locals.contains(&(&task.name, name)) {
                         // If a declared local has the same name as the `#[local]` struct, it's an
                         // direct error
-                        TaskLocal::Declared(_) => {
-                            lr_with_error.push(lr);
-                            lr_with_error.push(name);
+                        let message = format!(
+                            "Local resource {:?} is used by multiple tasks or collides with multiple definitions",
+                            lr.to_string(),
+                        );
+                        lr_with_error.push((lr, message.clone()));
+                        lr_with_error.push((name, message));
+                    } else {
+                        // Keep the first task that claims the resource and report every other one
+                        // along with it
+                        let first = lr_hash
+                            .entry(name.to_string())
+                            .or_insert((task, name, false));
+                        if first.0.name != task.name {
+                            let message = format!(
+                                "Local resource {:?} is claimed by both `{}` (priority {}) and `{}` (priority {})",
+                                name.to_string(),
+                                first.0.name,
+                                first.0.priority,
+                                task.name,
+                                task.priority,
+                            );
+
+                            if !first.2 {
+                                first.2 = true;
+                                lr_with_error.push((first.1, message.clone()));
+                            }
+                            lr_with_error.push((name, message));
                         }
                     }
                 }
@@ -160,36 +203,273 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
     }
 
     // Add error message for each use of the local resource
-    for resource in lr_with_error.clone() {
-        error.push(syn::Error::new(
+    for (resource, message) in lr_with_error {
+        error.push(AnalysisError::LocalCollision(syn::Error::new(
             resource.span(),
-            format!(
-                "Local resource {:?} is used by multiple tasks or collides with multiple definitions",
-                resource.to_string(),
-            ),
-        ));
+            message,
+        )));
+    }
+
+    // Check that task priorities are supported by the target
+    error.extend(
+        priority_errors(app, settings)
+            .into_iter()
+            .map(AnalysisError::PriorityTooHigh),
+    );
+
+    // Check that resource names are unique across shared and local resources
+    for shared in app.shared_resources.keys() {
+        if let Some((local, _)) = app.local_resources.get_key_value(shared) {
+            for resource in [shared, local] {
+                error.push(AnalysisError::DuplicateName(syn::Error::new(
+                    resource.span(),
+                    format!(
+                        "Resource {:?} is declared as both a shared and a local resource; resource names must be unique",
+                        resource.to_string(),
+                    ),
+                )));
+            }
+        }
+    }
+
+    // A zero capacity can only come from `Settings::default_capacity` as the parser rejects an
+    // explicit `capacity = 0`
+    for (name, task) in &app.software_tasks {
+        if task.args.capacity == 0 {
+            error.push(AnalysisError::QueueCapacity(syn::Error::new(
+                name.span(),
+                format!(
+                    "task {:?} has a capacity of 0; specify its `capacity` or use a non-zero default capacity",
+                    name.to_string(),
+                ),
+            )));
+        }
+    }
+
+    let mut channels = Channels::new();
+
+    for (name, spawnee) in &app.software_tasks {
+        let spawnee_prio = spawnee.args.priority;
+
+        let channel = channels.entry(spawnee_prio).or_default();
+        channel.tasks.insert(name.clone());
+    }
+
+    // No channel should ever be empty
+    debug_assert!(channels.values().all(|channel| !channel.tasks.is_empty()));
+
+    // Compute channel capacities
+    for (priority, channel) in channels.iter_mut() {
+        channel.input_types = channel
+            .tasks
+            .iter()
+            .flat_map(|name| app.software_tasks[name].inputs.iter())
+            .map(|input| input.ty.clone())
+            .collect();
+
+        let capacity = channel.tasks.iter().try_fold(0u8, |capacity, name| {
+            capacity.checked_add(app.software_tasks[name].args.capacity)
+        });
+
+        if let Some(capacity) = capacity {
+            channel.capacity = capacity;
+
+            // The queue needs one slot more than its capacity, and that must be indexable
+            if let Some(bits) = settings.queue_index_bits {
+                if u32::from(capacity) + 1 > (1u32 << cmp::min(bits, 31)) - 1 {
+                    let tasks = channel
+                        .tasks
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    for name in &channel.tasks {
+                        error.push(AnalysisError::QueueCapacity(syn::Error::new(
+                            name.span(),
+                            format!(
+                                "The dispatcher queue at priority {} (tasks: {}) has a capacity of {}, which doesn't fit a {}-bit queue index",
+                                priority, tasks, capacity, bits,
+                            ),
+                        )));
+                    }
+                }
+            }
+        } else {
+            // The dispatcher queue is shared by all tasks at this priority
+            for name in &channel.tasks {
+                error.push(AnalysisError::QueueCapacity(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "The combined dispatcher queue capacity of the tasks at priority {} exceeds the 255 slot limit",
+                        priority,
+                    ),
+                )));
+            }
+        }
+    }
+
+    // The hardware task bound to each interrupt; the parser rejects interrupts that are bound more
+    // than once
+    let interrupt_bindings = app
+        .hardware_tasks
+        .iter()
+        .map(|(name, task)| (task.args.binds.clone(), name.clone()))
+        .collect();
+
+    // Tasks that can be spawned, i.e. the ones that have a channel
+    let spawnable_tasks = channels
+        .values()
+        .flat_map(|channel| channel.tasks.iter().cloned())
+        .collect();
+
+    // Priority levels, between the lowest and the highest dispatcher priority, without dispatcher
+    let priority_gaps = match (channels.keys().next(), channels.keys().next_back()) {
+        (Some(lowest), Some(highest)) => (*lowest..*highest)
+            .filter(|priority| !channels.contains_key(priority))
+            .collect(),
+        _ => vec![],
+    };
+
+    // Priorities shared by hardware tasks and software task dispatchers
+    let mut shared_priorities = SharedPriorities::new();
+    for (name, task) in &app.hardware_tasks {
+        let priority = task.args.priority;
+
+        if channels.contains_key(&priority) {
+            shared_priorities.insert(priority);
+
+            if settings.error_on_shared_priorities {
+                error.push(AnalysisError::SharedPriority(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "Hardware task {:?} runs at priority {}, which is also used by a software task dispatcher",
+                        name.to_string(),
+                        priority,
+                    ),
+                )));
+            }
+        }
+    }
+
+    // Check that software task inputs can be stored in the message queues
+    for task in app.software_tasks.values() {
+        for input in &task.inputs {
+            let mut ty = &*input.ty;
+            while let Type::Paren(syn::TypeParen { elem, .. })
+            | Type::Group(syn::TypeGroup { elem, .. }) = ty
+            {
+                ty = elem;
+            }
+
+            let kind = match ty {
+                Type::ImplTrait(_) => "an `impl Trait` type",
+                Type::TraitObject(_) => "an unsized trait object",
+                Type::Slice(_) => "an unsized slice",
+                _ if borrows_non_static(ty) => "bound to a non-`'static` lifetime",
+                _ => continue,
+            };
+
+            error.push(AnalysisError::TaskInput(syn::Error::new_spanned(
+                input,
+                format!(
+                    "software task inputs are stored in a message queue so they can't be {}",
+                    kind,
+                ),
+            )));
+        }
+    }
+
+    // Resources that are declared but never accessed are rejected if requested, otherwise they are
+    // reported as warnings
+    let mut warnings = vec![];
+    let mut dead_resources = vec![];
+
+    for name in app.shared_resources.keys() {
+        if !task_resources_list
+            .iter()
+            .any(|task| task.shared.contains(name))
+        {
+            dead_resources.push(syn::Error::new(
+                name.span(),
+                format!("Shared resource {:?} is never used", name.to_string()),
+            ));
+        }
+    }
+
+    for name in app.local_resources.keys() {
+        if !task_resources_list
+            .iter()
+            .any(|task| task.local.contains(name) && !declared_locals.contains(&(&task.name, name)))
+        {
+            dead_resources.push(syn::Error::new(
+                name.span(),
+                format!("Local resource {:?} is never used", name.to_string()),
+            ));
+        }
+    }
+
+    if settings.error_on_dead_resources {
+        error.extend(dead_resources.into_iter().map(AnalysisError::DeadResource));
+    } else {
+        warnings.extend(dead_resources);
     }
 
     // Collect errors if any and return/halt
     if !error.is_empty() {
-        let mut err = error.iter().next().unwrap().clone();
-        error.iter().for_each(|e| err.combine(e.clone()));
-        return Err(err);
+        return Err(AnalysisError::from_errors(error));
     }
 
     // e. Location of resources
-    let mut shared_resource_locations = IndexMap::new();
+    let mut shared_resource_cores = IndexMap::new();
     let mut ownerships = Ownerships::new();
-    let mut sync_types = SyncTypes::new();
-    for (prio, name, access) in app.shared_resource_accesses() {
+    let mut sync_type_reasons = TypeReasons::new();
+    let mut resource_accessors = IndexMap::new();
+    let mut resource_priorities = IndexMap::new();
+    let mut access_stats = AccessStats::new();
+    let mut lowest_priorities = IndexMap::new();
+
+    // `check::app` rejects accesses to undeclared resources so, without shared resources, there's
+    // nothing to walk
+    let shared_resource_accesses = if app.shared_resources.is_empty() {
+        None
+    } else {
+        Some(app.shared_resource_accesses())
+    };
+    for (task, prio, name, access) in shared_resource_accesses.into_iter().flatten() {
         let res = app.shared_resources.get(name).expect("UNREACHABLE");
 
+        resource_accessors
+            .entry(name.clone())
+            .or_insert_with(Vec::new)
+            .push((task.clone(), access));
+
+        // Count the shared and exclusive accesses of each resource
+        let (shared, exclusive) = access_stats.entry(name.clone()).or_insert((0, 0));
+        if access.is_shared() {
+            *shared += 1;
+        } else {
+            *exclusive += 1;
+        }
+
         // (e)
-        // Add each resource to shared_resource_locations
-        shared_resource_locations.insert(name.clone(), Location::Owned);
+        // Add each resource to shared_resource_locations, tracking the cores it's accessed from.
+        // Tasks can't be pinned to a core in this version of the syntax, so all of them run on
+        // the default core and no core is ever recorded
+        shared_resource_cores
+            .entry(name.clone())
+            .or_insert_with(BTreeSet::new);
 
         // (c)
         if let Some(priority) = prio {
+            resource_priorities
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .push((task.clone(), priority));
+
+            let lowest = lowest_priorities.entry(name.clone()).or_insert(priority);
+            *lowest = cmp::min(*lowest, priority);
+
             if let Some(ownership) = ownerships.get_mut(name) {
                 match *ownership {
                     Ownership::Owned { priority: ceiling }
@@ -202,7 +482,11 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
                         };
 
                         if access.is_shared() {
-                            sync_types.insert(res.ty.clone());
+                            add_reason(
+                                &mut sync_type_reasons,
+                                res.ty.clone(),
+                                SendReason::SharedSync(name.clone()),
+                            );
                         }
                     }
 
@@ -218,83 +502,793 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
         }
     }
 
+    // Reject resources shared by tasks at the same priority, if requested
+    if settings.forbid_implicit_coownership {
+        let error = app
+            .shared_resource_accesses()
+            .filter_map(|(_, _, name, _)| match ownerships[name] {
+                Ownership::CoOwned { priority } => {
+                    Some(AnalysisError::CoOwnership(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "Shared resource {:?} is co-owned by several tasks at priority {}, which is forbidden",
+                            name.to_string(),
+                            priority,
+                        ),
+                    )))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if !error.is_empty() {
+            return Err(AnalysisError::from_errors(error));
+        }
+    }
+
+    // Invariant: the ceiling of a contended resource is the maximum priority among the tasks that
+    // access it, so no task runs above it; `Ownership::needs_lock` relies on this
+    debug_assert!(app
+        .shared_resource_accesses()
+        .all(
+            |(_, priority, name, _)| match (priority, ownerships.get(name)) {
+                (Some(priority), Some(ownership)) => priority <= ownership.priority(),
+                _ => true,
+            }
+        ));
+
+    // All used lock free resources have passed the priority check at this point
+    let lock_free: LockFreeResources = lock_free
+        .into_iter()
+        .filter(|name| ownerships.contains_key(*name))
+        .cloned()
+        .collect();
+
+    let shared_resource_locations = shared_resource_cores
+        .into_iter()
+        .map(|(name, cores)| (name, Location::from_cores(cores)))
+        .collect();
+
+    // Final ceiling of each used shared resource, regardless of its ownership
+    let ceilings = ownerships
+        .iter()
+        .map(|(name, ownership)| (name.clone(), ownership.priority()))
+        .collect();
+
+    // Shared resources only ever accessed by a single task, these could be local resources instead
+    let demotable_resources: Vec<_> = ownerships
+        .iter()
+        .filter(
+            |(_, ownership)| matches!(ownership, Ownership::Owned { priority } if *priority != 0),
+        )
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    for name in &demotable_resources {
+        let (declaration, _) = app
+            .shared_resources
+            .get_key_value(name)
+            .expect("UNREACHABLE");
+        let (task, _) = &resource_accessors[name][0];
+
+        warnings.push(syn::Error::new(
+            declaration.span(),
+            format!(
+                "Shared resource {:?} is only used by task {:?}; consider making it a local resource",
+                name.to_string(),
+                task.to_string(),
+            ),
+        ));
+    }
+
+    // Shared resources that are only accessed from a single priority level, by one or more tasks
+    let single_priority_resources = ownerships
+        .iter()
+        .filter(|(_, ownership)| !ownership.is_contended())
+        .map(|(name, ownership)| (name.clone(), ownership.priority()))
+        .collect();
+
+    // Shared resources accessed by several tasks that all run at the same priority, these never need
+    // a critical section even if they are not annotated `#[lock_free]`
+    let implicitly_lock_free = ownerships
+        .iter()
+        .filter(|(_, ownership)| matches!(ownership, Ownership::CoOwned { .. }))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    // Shared resources that never need a lock but are not annotated `#[lock_free]`
+    let suggest_lock_free = ownerships
+        .iter()
+        .filter(|(name, ownership)| !ownership.is_contended() && !lock_free.contains(*name))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    // `init` initializes every shared resource, so the ones that tasks only ever access through
+    // `&x` are read-only after `init`
+    let read_only_after_init = access_stats
+        .iter()
+        .filter(|(_, (_, exclusive))| *exclusive == 0)
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    // Resources written to by at least one task need `&mut self` accessors
+    let resource_mutability = access_stats
+        .iter()
+        .map(|(name, (_, exclusive))| {
+            let mutability = if *exclusive == 0 {
+                Mutability::Shared
+            } else {
+                Mutability::Exclusive
+            };
+
+            (name.clone(), mutability)
+        })
+        .collect();
+
+    // Software task inputs that look large, judging by their type
+    let mut large_inputs = vec![];
+    if let Some(threshold) = settings.large_input_threshold {
+        for (name, task) in &app.software_tasks {
+            for input in &task.inputs {
+                let elements = type_elements(&input.ty);
+
+                if elements > threshold {
+                    large_inputs.push((name.clone(), input.ty.clone()));
+
+                    warnings.push(syn::Error::new_spanned(
+                        input,
+                        format!(
+                            "this input of task {:?} has about {} elements, consider passing a reference instead of copying it into the message queue",
+                            name.to_string(),
+                            elements,
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Contended resources whose ceiling is too far above the lowest priority that accesses them
+    let mut inversion_warnings = InversionWarnings::new();
+    if let Some(threshold) = settings.priority_inversion_threshold {
+        for (name, ownership) in &ownerships {
+            let ceiling = ownership.priority();
+            let inversion = ceiling - lowest_priorities[name];
+
+            if ownership.is_contended() && inversion > threshold {
+                inversion_warnings.insert(name.clone(), inversion);
+
+                warnings.push(syn::Error::new(
+                    app.shared_resources.get_key_value(name).expect("UNREACHABLE").0.span(),
+                    format!(
+                        "Shared resource {:?} has a ceiling of {}, {} levels above the lowest priority that uses it",
+                        name.to_string(),
+                        ceiling,
+                        inversion,
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Locking a contended resource only needs to mask the tasks that access it and may preempt
+    // the task that holds the lock; these run above the lowest priority that accesses it
+    let mask_levels = resource_priorities
+        .iter()
+        .filter(|(name, _)| ownerships[*name].is_contended())
+        .map(|(name, accessors)| {
+            let lowest = lowest_priorities[name];
+            let levels = accessors
+                .iter()
+                .map(|(_, priority)| *priority)
+                .filter(|priority| *priority > lowest)
+                .collect();
+
+            (name.clone(), levels)
+        })
+        .collect();
+
+    // Shared resources contended only by `idle` and one other task
+    let idle_contended = app
+        .idle
+        .as_ref()
+        .map(|idle| {
+            resource_accessors
+                .iter()
+                .filter(|(name, accessors)| {
+                    ownerships[*name].is_contended()
+                        && accessors.len() == 2
+                        && accessors.iter().any(|(task, _)| *task == idle.name)
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Every priority level used in the application
+    let all_priorities = app
+        .tasks_with_kind()
+        .into_iter()
+        .map(|(_, _, priority)| priority)
+        .chain(
+            ownerships
+                .values()
+                .filter(|ownership| ownership.is_contended())
+                .map(|ownership| ownership.priority()),
+        )
+        .collect::<BTreeSet<_>>();
+
+    // Highest priority used in the application
+    let max_priority = all_priorities.iter().next_back().cloned().unwrap_or(0);
+
+    // Contended resources whose lock masks every task of the application
+    let global_locks = ownerships
+        .iter()
+        .filter(|(_, ownership)| {
+            matches!(ownership, Ownership::Contended { ceiling } if *ceiling == max_priority)
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    // Shared resources that need a lock at each task priority level
+    let resources_by_priority_lock = app
+        .tasks_with_kind()
+        .into_iter()
+        .map(|(_, _, priority)| {
+            let resources = ownerships
+                .iter()
+                // `needs_lock` expects a priority that's not above the ceiling
+                .filter(|(_, ownership)| {
+                    ownership.priority() >= priority && ownership.needs_lock(priority)
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            (priority, resources)
+        })
+        .collect();
+
+    // Shared resources that each task needs to lock
+    let locks: Locks = task_resources_list
+        .iter()
+        .map(|task| {
+            let mut resources = task
+                .shared
+                .iter()
+                .filter(|name| ownerships[*name].needs_lock(task.priority))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // Sort by descending ceiling, then by name, so that all tasks agree on a single global
+            // lock order
+            resources.sort_by(|a, b| {
+                ownerships[b]
+                    .priority()
+                    .cmp(&ownerships[a].priority())
+                    .then_with(|| a.cmp(b))
+            });
+
+            (task.name.clone(), resources)
+        })
+        .collect();
+
+    // Contended resources that `idle` needs to lock
+    let idle_locks = app
+        .idle
+        .as_ref()
+        .map(|idle| locks[&idle.name].iter().cloned().collect())
+        .unwrap_or_default();
+
+    // Highest ceiling among the resources that each task locks
+    let task_ceilings: IndexMap<Task, u8> = locks
+        .iter()
+        .filter_map(|(task, resources)| {
+            resources
+                .iter()
+                .map(|name| ownerships[name].priority())
+                .max()
+                .map(|ceiling| (task.clone(), ceiling))
+        })
+        .collect();
+
+    // Hardware tasks that need to raise the priority above their own to lock some resource
+    let entry_ceilings = app
+        .hardware_tasks
+        .iter()
+        .filter_map(|(name, task)| {
+            task_ceilings
+                .get(name)
+                .filter(|ceiling| **ceiling > task.args.priority)
+                .map(|ceiling| (name.clone(), *ceiling))
+        })
+        .collect();
+
     // Create the list of used local resource Idents
     let mut local_resource_locations = IndexMap::new();
+    let mut local_resource_owners = LocalResourceOwners::new();
+
+    for task in task_resources_list {
+        for local in task.local {
+            // Local resources are only accessed by their owner, which runs on the default core
+            local_resource_locations.insert(local.clone(), Location::from_cores(BTreeSet::new()));
+
+            // Locals declared in the task itself may reuse the same name in other tasks, but the
+            // ones declared in `#[local]` have a single owner
+            if app.local_resources.contains_key(&local) {
+                local_resource_owners.insert(local.clone(), task.name.clone());
 
-    for (_, _, locals, _) in task_resources_list {
-        for (local, _) in locals {
-            local_resource_locations.insert(local.clone(), Location::Owned);
+                // Local resources are always accessed through a mutable reference
+                resource_accessors
+                    .entry(local.clone())
+                    .or_insert_with(Vec::new)
+                    .push((task.name.clone(), Access::Exclusive));
+            }
+        }
+    }
+
+    // Software tasks spawned by each task, as far as they can be told from the task bodies
+    let spawn_graph: SpawnGraph = Some((&app.init.name, &app.init.stmts))
+        .into_iter()
+        .chain(app.idle.iter().map(|idle| (&idle.name, &idle.stmts)))
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.stmts)),
+        )
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.stmts)),
+        )
+        .map(|(name, stmts)| {
+            let mut spawnees = BTreeSet::new();
+            spawned_tasks(app, quote!(#(#stmts)*), &mut spawnees);
+            (name.clone(), spawnees)
+        })
+        .collect();
+
+    // A task that spawns itself with a queue of a single message can fill its own queue; e.g. if
+    // it spawns itself again before the message it was dispatched from is released
+    for (name, task) in &app.software_tasks {
+        let spawns_itself = spawn_graph
+            .get(name)
+            .map(|spawnees| spawnees.contains(name))
+            .unwrap_or(false);
+
+        if spawns_itself && task.args.capacity == 1 {
+            warnings.push(syn::Error::new(
+                name.span(),
+                format!(
+                    "task {:?} spawns itself but has a capacity of 1; its message queue may be full when it does",
+                    name.to_string(),
+                ),
+            ));
         }
     }
 
     // Most shared resources need to be `Send`
-    let mut send_types = SendTypes::new();
+    let mut send_type_reasons = TypeReasons::new();
     let owned_by_idle = Ownership::Owned { priority: 0 };
     for (name, res) in app.shared_resources.iter() {
         // handle not owned by idle
-        if ownerships
-            .get(name)
-            .map(|ownership| *ownership != owned_by_idle)
-            .unwrap_or(false)
-        {
-            send_types.insert(res.ty.clone());
+        match ownerships.get(name) {
+            Some(ownership) if *ownership != owned_by_idle => {
+                // resources that aren't contended are only moved out of `init`
+                let reason = if ownership.is_contended() {
+                    SendReason::SharedAcrossPriorities(name.clone())
+                } else {
+                    SendReason::InitHandoff(name.clone())
+                };
+
+                add_reason(&mut send_type_reasons, res.ty.clone(), reason);
+            }
+            _ => {}
         }
     }
 
     // Most local resources need to be `Send` as well
     for (name, res) in app.local_resources.iter() {
-        if let Some(idle) = &app.idle {
-            // Only Send if not in idle
-            if idle.args.local_resources.get(name).is_none() {
-                send_types.insert(res.ty.clone());
-            }
-        } else {
-            send_types.insert(res.ty.clone());
+        // Only Send if not in idle
+        let in_idle = app
+            .idle
+            .as_ref()
+            .map(|idle| idle.args.local_resources.get(name).is_some())
+            .unwrap_or(false);
+
+        if !in_idle {
+            add_reason(
+                &mut send_type_reasons,
+                res.ty.clone(),
+                SendReason::InitHandoff(name.clone()),
+            );
         }
     }
 
-    let mut channels = Channels::new();
-
+    // All inputs are send as we do not know from where they may be spawned. The `spawn_graph` can't
+    // help here: it only records the spawns that appear in the task bodies, whereas tasks can also
+    // be spawned from functions outside of the `#[app]` module, or from `extern` tasks whose bodies
+    // are not available
     for (name, spawnee) in &app.software_tasks {
-        let spawnee_prio = spawnee.args.priority;
-
-        let channel = channels.entry(spawnee_prio).or_default();
-        channel.tasks.insert(name.clone());
-
-        // All inputs are now send as we do not know from where they may be spawned.
         spawnee.inputs.iter().for_each(|input| {
-            send_types.insert(input.ty.clone());
+            add_reason(
+                &mut send_type_reasons,
+                input.ty.clone(),
+                SendReason::TaskInput(name.clone()),
+            );
         });
     }
 
-    // No channel should ever be empty
-    debug_assert!(channels.values().all(|channel| !channel.tasks.is_empty()));
+    let send_types = send_type_reasons.keys().cloned().collect();
+    let sync_types = sync_type_reasons.keys().cloned().collect();
 
-    // Compute channel capacities
-    for channel in channels.values_mut() {
-        channel.capacity = channel
-            .tasks
-            .iter()
-            .map(|name| app.software_tasks[name].args.capacity)
-            .sum();
-    }
+    // Documentation of the resources, i.e. the value of their `#[doc = ".."]` attributes
+    let resource_docs = app
+        .shared_resources
+        .iter()
+        .map(|(name, res)| (name, &res.attrs))
+        .chain(
+            app.local_resources
+                .iter()
+                .map(|(name, res)| (name, &res.attrs)),
+        )
+        .filter_map(|(name, attrs)| {
+            let docs = attrs
+                .iter()
+                .filter_map(|attr| match attr.parse_meta() {
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                        path,
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    })) if path.is_ident("doc") => Some(doc.value()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            if docs.is_empty() {
+                None
+            } else {
+                Some((name.clone(), docs))
+            }
+        })
+        .collect();
+
+    // Every resource declared in `#[shared]` or `#[local]` is returned by `init`; locals declared in
+    // the task argument, e.g. `local = [x: u32 = 0]`, have a static initializer instead
+    let late_resources = app
+        .shared_resources
+        .keys()
+        .chain(app.local_resources.keys())
+        .cloned()
+        .collect();
 
     Ok(Analysis {
         channels,
+        spawnable_tasks,
+        priority_gaps,
+        interrupt_bindings,
         shared_resource_locations,
         local_resource_locations,
+        local_resource_owners,
         tasks,
         ownerships,
+        ceilings,
+        lock_free,
+        late_resources,
+        resource_docs,
+        locks,
+        resources_by_priority_lock,
+        idle_locks,
+        inversion_warnings,
+        large_inputs,
+        demotable_resources,
+        implicitly_lock_free,
+        suggest_lock_free,
+        single_task_resources,
+        global_locks,
+        single_priority_resources,
+        idle_contended,
+        entry_ceilings,
+        mask_levels,
+        resource_mutability,
+        read_only_after_init,
+        access_stats,
         send_types,
         sync_types,
+        send_type_reasons,
+        sync_type_reasons,
+        shared_priorities,
+        spawn_graph,
+        warnings,
+        max_priority,
+        all_priorities,
+        task_ceilings,
+        task_priorities,
+        resource_accessors,
+        resource_priorities,
     })
 }
 
-/// Priority ceiling
-pub type Ceiling = Option<u8>;
+/// Tasks that access each *used* shared resource, along with their priorities
+///
+/// Resources are listed in declaration order
+pub fn contention_graph(app: &App) -> Vec<(Resource, Vec<(Task, Priority)>)> {
+    let mut graph = app
+        .shared_resources
+        .keys()
+        .map(|name| (name, vec![]))
+        .collect::<IndexMap<_, _>>();
 
-/// Task priority
-pub type Priority = u8;
+    for (task, priority, name, _) in app.shared_resource_accesses() {
+        if let (Some(priority), Some(tasks)) = (priority, graph.get_mut(name)) {
+            tasks.push((task.clone(), priority));
+        }
+    }
+
+    graph
+        .into_iter()
+        .filter(|(_, tasks)| !tasks.is_empty())
+        .map(|(name, tasks)| (name.clone(), tasks))
+        .collect()
+}
+
+/// The combined capacity of all the `channels`
+///
+/// The capacities are added as `usize` so the sum can't overflow
+pub fn total_capacity(channels: &Channels) -> usize {
+    channels
+        .values()
+        .map(|channel| usize::from(channel.capacity))
+        .sum()
+}
+
+/// An error found while analyzing an application
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AnalysisError {
+    /// A `#[lock_free]` resource is used by tasks at different priorities
+    LockFree(syn::Error),
+
+    /// A local resource is used by several tasks, or collides with a local declared in a task
+    LocalCollision(syn::Error),
+
+    /// The same name is used by a shared and a local resource
+    DuplicateName(syn::Error),
+
+    /// A task priority is out of range, see `validate_priorities`
+    PriorityTooHigh(syn::Error),
+
+    /// The queue of a dispatcher is too large
+    QueueCapacity(syn::Error),
+
+    /// A hardware task runs at the priority of a dispatcher, see
+    /// `Settings::error_on_shared_priorities`
+    SharedPriority(syn::Error),
+
+    /// A software task input can't be stored in a message queue
+    TaskInput(syn::Error),
+
+    /// A resource is never used, see `Settings::error_on_dead_resources`
+    DeadResource(syn::Error),
+
+    /// A resource is co-owned by tasks at the same priority, see
+    /// `Settings::forbid_implicit_coownership`
+    CoOwnership(syn::Error),
+
+    /// A `#[single_task]` resource is used by more than one task
+    SingleTask(syn::Error),
+
+    /// Several errors, none of which is `Multiple`
+    Multiple(Vec<AnalysisError>),
+}
+
+impl AnalysisError {
+    // With the `source-order` feature the errors are sorted by their position in the source so
+    // that they are reported top to bottom, whatever pass found them. This needs the
+    // `span-locations` feature of proc-macro2, which only knows the positions when it doesn't run
+    // inside a proc macro (e.g. in tests or in tools that parse the source themselves); otherwise
+    // every error starts at 0:0 and, as the sort is stable, the errors keep the order of the
+    // passes that found them. All spans come from the same `#[app]` input, so comparing line and
+    // column is enough
+    fn from_errors(mut errors: Vec<AnalysisError>) -> Self {
+        #[cfg(feature = "source-order")]
+        errors.sort_by_key(|error| {
+            let start = error.span().start();
+            (start.line, start.column)
+        });
+
+        if errors.len() == 1 {
+            errors.pop().expect("UNREACHABLE")
+        } else {
+            AnalysisError::Multiple(errors)
+        }
+    }
+
+    #[cfg(feature = "source-order")]
+    fn span(&self) -> Span {
+        match self {
+            AnalysisError::LockFree(e)
+            | AnalysisError::LocalCollision(e)
+            | AnalysisError::DuplicateName(e)
+            | AnalysisError::PriorityTooHigh(e)
+            | AnalysisError::QueueCapacity(e)
+            | AnalysisError::SharedPriority(e)
+            | AnalysisError::TaskInput(e)
+            | AnalysisError::DeadResource(e)
+            | AnalysisError::CoOwnership(e)
+            | AnalysisError::SingleTask(e) => e.span(),
+
+            AnalysisError::Multiple(errors) => errors
+                .first()
+                .map(AnalysisError::span)
+                .unwrap_or_else(Span::call_site),
+        }
+    }
+}
+
+impl From<AnalysisError> for syn::Error {
+    fn from(error: AnalysisError) -> Self {
+        match error {
+            AnalysisError::LockFree(e)
+            | AnalysisError::LocalCollision(e)
+            | AnalysisError::DuplicateName(e)
+            | AnalysisError::PriorityTooHigh(e)
+            | AnalysisError::QueueCapacity(e)
+            | AnalysisError::SharedPriority(e)
+            | AnalysisError::TaskInput(e)
+            | AnalysisError::DeadResource(e)
+            | AnalysisError::CoOwnership(e)
+            | AnalysisError::SingleTask(e) => e,
+
+            AnalysisError::Multiple(errors) => {
+                let mut errors = errors.into_iter().map(syn::Error::from);
+                let mut err = errors.next().expect("UNREACHABLE");
+                errors.for_each(|e| err.combine(e));
+                err
+            }
+        }
+    }
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::LockFree(e)
+            | AnalysisError::LocalCollision(e)
+            | AnalysisError::DuplicateName(e)
+            | AnalysisError::PriorityTooHigh(e)
+            | AnalysisError::QueueCapacity(e)
+            | AnalysisError::SharedPriority(e)
+            | AnalysisError::TaskInput(e)
+            | AnalysisError::DeadResource(e)
+            | AnalysisError::CoOwnership(e)
+            | AnalysisError::SingleTask(e) => fmt::Display::fmt(e, f),
+
+            // one message per line, in the order the errors are reported
+            AnalysisError::Multiple(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str("\n")?;
+                    }
+
+                    fmt::Display::fmt(error, f)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Converts a `logical` priority into the value of an NVIC priority register with `nvic_prio_bits`
+/// implemented priority bits
+///
+/// NVIC priorities are inverted, lower values are more urgent, and stored in the most significant
+/// bits of the register. Returns `None` if `nvic_prio_bits` is not in the range 1...8 or if
+/// `logical` is not in the range 1...2^`nvic_prio_bits`
+pub fn logical_to_hw_priority(logical: u8, nvic_prio_bits: u8) -> Option<u8> {
+    if nvic_prio_bits == 0 || nvic_prio_bits > 8 {
+        return None;
+    }
+
+    let levels = 1u16 << nvic_prio_bits;
+    if logical == 0 || u16::from(logical) > levels {
+        return None;
+    }
+
+    Some(((levels - u16::from(logical)) << (8 - nvic_prio_bits)) as u8)
+}
+
+/// Checks that the priority of every hardware and software task is in the range 1...`max`, where
+/// `max` is `Settings::max_priority` or 255 if unset
+///
+/// The returned error lists every task whose priority is out of range
+pub fn validate_priorities(app: &App, settings: &Settings) -> Result<(), syn::Error> {
+    let mut errors = priority_errors(app, settings).into_iter();
+
+    if let Some(mut error) = errors.next() {
+        errors.for_each(|e| error.combine(e));
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+fn priority_errors(app: &App, settings: &Settings) -> Vec<syn::Error> {
+    let max_priority = settings.max_priority.unwrap_or(u8::MAX);
+
+    app.hardware_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority))
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.priority)),
+        )
+        .filter_map(|(name, priority)| {
+            if priority == 0 {
+                Some(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "Task {:?} has priority 0, which is reserved for `idle`",
+                        name.to_string(),
+                    ),
+                ))
+            } else if priority > max_priority {
+                Some(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "Task {:?} has priority {}, which exceeds the maximum priority of {}",
+                        name.to_string(),
+                        priority,
+                        max_priority,
+                    ),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Checks that there are enough dispatcher interrupts, `available`, to service every distinct
+/// software task priority
+pub fn check_dispatchers(app: &App, available: usize) -> Result<(), syn::Error> {
+    let priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .collect::<BTreeSet<_>>();
+
+    if priorities.len() <= available {
+        return Ok(());
+    }
+
+    // point at a task whose priority can not be given a dispatcher
+    let unserviced = priorities.iter().nth(available).cloned();
+    let span = app
+        .software_tasks
+        .iter()
+        .find(|(_, task)| Some(task.args.priority) == unserviced)
+        .map(|(name, _)| name.span())
+        .unwrap_or_else(Span::call_site);
+
+    Err(syn::Error::new(
+        span,
+        format!(
+            "software tasks need {} dispatchers but only {} are available",
+            priorities.len(),
+            available
+        ),
+    ))
+}
+
+/// Priority ceiling
+pub type Ceiling = Option<u8>;
+
+/// Task priority
+pub type Priority = u8;
 
 /// Resource name
 pub type Resource = Ident;
@@ -305,34 +1299,623 @@ pub type Task = Ident;
 /// List of tasks names
 pub type Tasks = Vec<Ident>;
 
+/// Kind of task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TaskKind {
+    /// The `idle` context
+    Idle,
+
+    /// A hardware task: `#[task(binds = ..)]`
+    Hardware,
+
+    /// A software task: `#[task]`
+    Software,
+}
+
+/// The resources accessed by a task
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskResources {
+    /// The name of the task
+    pub name: Task,
+
+    /// The shared resources accessed by the task
+    pub shared: Vec<Resource>,
+
+    /// The local resources of the task, both the ones declared in `#[local]` and in the task
+    /// itself
+    pub local: Vec<Resource>,
+
+    /// The priority of the task
+    pub priority: Priority,
+}
+
+/// The software tasks dispatched at a priority level, see `Analysis::dispatcher_summaries`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DispatcherSummary {
+    /// The priority of the dispatcher
+    pub priority: Priority,
+
+    /// The combined capacity of the tasks
+    pub capacity: u8,
+
+    /// The tasks, in lexical order of their names as in `Channel::tasks`
+    pub tasks: Vec<Task>,
+}
+
+/// The differences between two analyses, see `Analysis::diff`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnalysisDiff {
+    /// Tasks that only the new analysis has
+    pub added_tasks: Vec<Task>,
+
+    /// Tasks that only the old analysis has
+    pub removed_tasks: Vec<Task>,
+
+    /// Shared resources whose ownership changed, along with the old and the new ownership; `None`
+    /// if the resource is not used by one of the analyses
+    pub ownerships: Vec<(Resource, Option<Ownership>, Option<Ownership>)>,
+
+    /// Dispatcher priorities whose queue capacity changed, along with the old and the new
+    /// capacity; `None` if there's no dispatcher at this priority in one of the analyses
+    pub capacities: Vec<(Priority, Option<u8>, Option<u8>)>,
+}
+
+impl AnalysisDiff {
+    /// Whether both analyses have the same tasks, ownerships and dispatcher capacities
+    pub fn is_empty(&self) -> bool {
+        self.added_tasks.is_empty()
+            && self.removed_tasks.is_empty()
+            && self.ownerships.is_empty()
+            && self.capacities.is_empty()
+    }
+}
+
 /// The result of analyzing an RTIC application
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Analysis {
     /// SPSC message channels
     pub channels: Channels,
 
+    /// Tasks that can be spawned, i.e. all software tasks
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub spawnable_tasks: BTreeSet<Task>,
+
+    /// Priorities, in ascending order, that no software task uses but that lie between the lowest
+    /// and the highest software task priority
+    ///
+    /// This is advisory only
+    pub priority_gaps: Vec<Priority>,
+
+    /// The hardware task bound to each interrupt
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map_of_tokens"))]
+    pub interrupt_bindings: IndexMap<Ident, Task>,
+
     /// Location of all *used* shared resources
     ///
     /// If a resource is not listed here it means that's a "dead" (never accessed) resource and the
     /// backend should not generate code for it
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
     pub shared_resource_locations: SharedResourceLocations,
 
     /// Location of all *used* local resources
     ///
     /// If a resource is not listed here it means that's a "dead" (never accessed) resource and the
     /// backend should not generate code for it
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
     pub local_resource_locations: LocalResourceLocations,
 
+    /// The task that owns each *used* local resource declared in `#[local]`
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map_of_tokens"))]
+    pub local_resource_owners: LocalResourceOwners,
+
     /// A vector containing all task names
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
     pub tasks: Tasks,
 
     /// Resource ownership
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
     pub ownerships: Ownerships,
 
+    /// Priority ceiling of all *used* shared resources
+    ///
+    /// For resources that are not contended the ceiling is the priority of the owner(s)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub ceilings: Ceilings,
+
+    /// *Used* shared resources annotated `#[lock_free]`
+    ///
+    /// All the tasks that access these resources run at the same priority so the resources can be
+    /// accessed without a critical section
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub lock_free: LockFreeResources,
+
+    /// Resources that are initialized at runtime, i.e. the ones that `init` must return
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub late_resources: LateResources,
+
+    /// The documentation of each shared and local resource that has any, one entry per
+    /// `#[doc = ".."]` attribute (e.g. per `///` line)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub resource_docs: ResourceDocs,
+
+    /// Shared resources that each task needs to lock, i.e. the contended resources whose ceiling
+    /// is above the priority of the task
+    ///
+    /// The resources are in lock order: by descending ceiling and then by name. As this order is
+    /// the same for every task, nested locks are always taken in a consistent order. Every task is
+    /// listed here, even if it doesn't need to lock any resource
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map_of_seqs"))]
+    pub locks: Locks,
+
+    /// Shared resources that need a lock at each priority level, i.e. the contended resources
+    /// whose ceiling is above that priority
+    ///
+    /// Every priority level used by `idle` or a task is listed here, even if it doesn't need to lock
+    /// any resource
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::sets_by_priority"))]
+    pub resources_by_priority_lock: BTreeMap<Priority, BTreeSet<Resource>>,
+
+    /// Contended shared resources that `idle` needs to lock
+    ///
+    /// As `idle` runs at the lowest priority every one of these locks raises the priority
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub idle_locks: BTreeSet<Resource>,
+
+    /// Contended shared resources whose ceiling exceeds the lowest priority that accesses them by
+    /// more than `Settings::priority_inversion_threshold`, along with that difference
+    ///
+    /// Locking these resources in the low priority tasks can block higher priority tasks for
+    /// longer than expected. This is advisory only
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub inversion_warnings: InversionWarnings,
+
+    /// Software task inputs that look large, i.e. whose type has more elements, by counting array
+    /// lengths and tuple fields, than `Settings::large_input_threshold`
+    ///
+    /// This is advisory only; the actual size of the types is not known at this point
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::pairs"))]
+    pub large_inputs: Vec<(Task, Box<Type>)>,
+
+    /// Shared resources that are only accessed by a single task, other than `idle`
+    ///
+    /// These resources could be turned into local resources
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub demotable_resources: Vec<Resource>,
+
+    /// Shared resources that are accessed by several tasks which all run at the same priority
+    ///
+    /// These resources can be accessed without a critical section, whether or not they are
+    /// annotated `#[lock_free]`
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub implicitly_lock_free: BTreeSet<Resource>,
+
+    /// *Used* shared resources that are not contended but are not annotated `#[lock_free]`
+    ///
+    /// Annotating these resources `#[lock_free]` gives their tasks a cheaper accessor. This is
+    /// advisory only
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub suggest_lock_free: BTreeSet<Resource>,
+
+    /// Shared resources annotated `#[single_task]`, all of which are accessed by a single task
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub single_task_resources: BTreeSet<Resource>,
+
+    /// Contended shared resources whose ceiling is the highest priority of the application
+    ///
+    /// Locking one of these resources blocks every other task, like a global critical section.
+    /// This is advisory only
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub global_locks: BTreeSet<Resource>,
+
+    /// *Used* shared resources that are only accessed from a single priority level, i.e. the
+    /// resources that are not contended, along with that priority
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub single_priority_resources: IndexMap<Resource, Priority>,
+
+    /// Shared resources that are contended by `idle` and exactly one other task
+    ///
+    /// `idle` can access these resources without a critical section by, e.g., reading them with
+    /// interrupts disabled; the other task never needs to lock them
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub idle_contended: BTreeSet<Resource>,
+
+    /// Hardware tasks that lock resources whose ceiling is above their own priority, along with
+    /// the highest of those ceilings
+    ///
+    /// Hardware tasks that don't need to raise their priority are not listed
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub entry_ceilings: EntryCeilings,
+
+    /// The priorities that need to be masked to lock each contended shared resource
+    ///
+    /// These are the priorities of the tasks that access the resource, except the lowest one; the
+    /// highest of them is the ceiling of the resource
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub mask_levels: MaskLevels,
+
+    /// *Used* shared resources that tasks only access through shared references (`&x`)
+    ///
+    /// These resources are only written to by `init`, when it initializes them. As every resource
+    /// declared in `#[shared]` is initialized at runtime by `init` there are no resources that are
+    /// never written to; these are the ones closest to being constants
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub read_only_after_init: BTreeSet<Resource>,
+
+    /// Whether tasks access each *used* shared resource only through shared references (`&x`) or
+    /// also exclusively (`x`)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub resource_mutability: ResourceMutability,
+
+    /// Number of shared (`&x`) and exclusive (`x`) accesses, in that order, of all *used* shared
+    /// resources
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub access_stats: AccessStats,
+
     /// These types must implement the `Send` trait
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
     pub send_types: SendTypes,
 
     /// These types must implement the `Sync` trait
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
     pub sync_types: SyncTypes,
+
+    /// Why each of the `send_types` must implement the `Send` trait
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub send_type_reasons: TypeReasons,
+
+    /// Why each of the `sync_types` must implement the `Sync` trait
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map"))]
+    pub sync_type_reasons: TypeReasons,
+
+    /// Priorities used by both hardware tasks and software task dispatchers
+    pub shared_priorities: SharedPriorities,
+
+    /// The software tasks that each task, including `init`, spawns
+    ///
+    /// These edges are found by looking for paths like `foo::spawn`, `foo::spawn_after` and
+    /// `foo::spawn_at` in the body of the tasks. The body of tasks declared in `extern` blocks is
+    /// not available so these tasks never spawn anything according to this graph
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::map_of_sets"))]
+    pub spawn_graph: SpawnGraph,
+
+    /// Non-fatal diagnostics, e.g. about dead resources, that the front-end can report as warnings
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::messages"))]
+    pub warnings: Vec<syn::Error>,
+
+    // Private caches behind the accessor methods; they only repeat what the public fields
+    // already say so none of them is serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    max_priority: Priority,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    all_priorities: BTreeSet<Priority>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    task_ceilings: IndexMap<Task, u8>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    task_priorities: IndexMap<Task, Priority>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    resource_accessors: IndexMap<Resource, Vec<(Task, Access)>>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    resource_priorities: IndexMap<Resource, Vec<(Task, Priority)>>,
+}
+
+impl Analysis {
+    /// An analysis without any task, channel or resource
+    ///
+    /// Its public fields can be filled in by hand, e.g. to test a backend without parsing a full
+    /// application. Some accessors are computed by `analyze::app` and can't be derived from the
+    /// public fields, so on such an analysis they keep answering as if there were no tasks:
+    /// `max_priority` returns 0, `all_priorities` is empty, `task_priority` and `task_ceiling`
+    /// return `None`, and `resource_accessors` and `contenders` return empty lists. The accessors
+    /// that are computed from the public fields, like `needs_locking` and `ceiling_for`, are
+    /// reliable
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// The highest priority used in the application
+    ///
+    /// This considers the priorities of all hardware and software tasks as well as the ceilings of
+    /// contended resources. Returns 0 if the application only contains `init` and `idle`
+    pub fn max_priority(&self) -> u8 {
+        self.max_priority
+    }
+
+    /// Every priority level used in the application, in ascending order
+    ///
+    /// This includes the priorities of `idle` and all hardware and software tasks as well as the
+    /// ceilings of contended resources. Priority 0 is the level of `idle` (and `init`), it's only
+    /// listed if the application contains `idle`
+    pub fn all_priorities(&self) -> BTreeSet<Priority> {
+        self.all_priorities.clone()
+    }
+
+    /// Whether any shared resource is contended, i.e. whether the application needs locks at all
+    pub fn needs_locking(&self) -> bool {
+        self.ownerships.values().any(Ownership::is_contended)
+    }
+
+    /// Whether the given shared `resource` needs a lock and, if so, its ceiling
+    pub fn ceiling_for(&self, resource: &Resource) -> CeilingInfo {
+        match self.ownerships.get(resource) {
+            None => CeilingInfo::Unknown,
+            Some(Ownership::Contended { ceiling }) => CeilingInfo::Lock(*ceiling),
+            Some(Ownership::Owned { .. }) | Some(Ownership::CoOwned { .. }) => CeilingInfo::NoLock,
+        }
+    }
+
+    /// The priority of `task`, 0 for `init` and `idle`, or `None` if there's no such task
+    pub fn task_priority(&self, task: &Task) -> Option<u8> {
+        self.task_priorities.get(task).cloned()
+    }
+
+    /// The highest ceiling among the resources that `task` needs to lock, or `None` if it doesn't
+    /// need to lock any resource
+    pub fn task_ceiling(&self, task: &Task) -> Option<u8> {
+        self.task_ceilings.get(task).cloned()
+    }
+
+    /// Tasks that contend the given shared `resource`, along with their priority, sorted by
+    /// priority
+    ///
+    /// Returns an empty list if the resource is not contended, or if it's unknown or dead
+    pub fn contenders(&self, resource: &Resource) -> Vec<(Task, Priority)> {
+        if !matches!(self.ceiling_for(resource), CeilingInfo::Lock(_)) {
+            return vec![];
+        }
+
+        let mut contenders = self
+            .resource_priorities
+            .get(resource)
+            .cloned()
+            .unwrap_or_default();
+        contenders.sort_by_key(|(_, priority)| *priority);
+        contenders
+    }
+
+    /// Whether `resource` is a *used* shared or local resource, or `None` if it's unknown or dead
+    pub fn resource_kind(&self, resource: &Resource) -> Option<ResourceKind> {
+        if self.shared_resource_locations.contains_key(resource) {
+            Some(ResourceKind::Shared)
+        } else if self.local_resource_locations.contains_key(resource) {
+            Some(ResourceKind::Local)
+        } else {
+            None
+        }
+    }
+
+    /// Tasks that access the given shared or local `resource`, along with the kind of access
+    ///
+    /// Local resources are always accessed exclusively. Locals declared in a task's own `local`
+    /// list are not included as several tasks may declare one with the same name. Returns an empty
+    /// list for unknown or dead resources
+    pub fn resource_accessors(&self, resource: &Resource) -> Vec<(Task, Access)> {
+        self.resource_accessors
+            .get(resource)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The maximum number of messages that can be pending at the given `priority`, i.e. the
+    /// capacity of the dispatcher queue shared by all the software tasks at that priority
+    ///
+    /// Returns `None` if there's no software task at this priority
+    pub fn dispatcher_depth(&self, priority: Priority) -> Option<u8> {
+        self.channels.get(&priority).map(|channel| channel.capacity)
+    }
+
+    /// Types that must implement both the `Send` and the `Sync` traits, in the order of
+    /// `send_types`
+    pub fn send_and_sync_types(&self) -> Vec<Box<Type>> {
+        self.send_types
+            .intersection(&self.sync_types)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the application contains nothing but `init`, without any local resources
+    ///
+    /// That is, there's no `idle`, no hardware or software tasks and no *used* resources
+    pub fn is_empty(&self) -> bool {
+        // `init` is always the first task, if any; `Analysis::empty` has no tasks at all
+        self.tasks.len() <= 1
+            && self.channels.is_empty()
+            && self.shared_resource_locations.is_empty()
+            && self.local_resource_locations.is_empty()
+    }
+
+    /// A hash of the channels, ownerships, resource locations and `Send` / `Sync` types
+    ///
+    /// Types are hashed token by token, not through their string form whose spacing is up to the
+    /// compiler, so the hash doesn't depend on the process or the compiler version and identical
+    /// applications always have the same fingerprint
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+
+        hasher.write_len(self.channels.len());
+        for (priority, channel) in &self.channels {
+            hasher.write(&[*priority, channel.capacity]);
+
+            hasher.write_len(channel.tasks.len());
+            for task in &channel.tasks {
+                hasher.write_str(&task.to_string());
+            }
+
+            hasher.write_len(channel.input_types.len());
+            for ty in &channel.input_types {
+                hasher.write_tokens(quote!(#ty));
+            }
+        }
+
+        hasher.write_len(self.ownerships.len());
+        for (name, ownership) in &self.ownerships {
+            hasher.write_str(&name.to_string());
+            match *ownership {
+                Ownership::Owned { priority } => hasher.write(&[0, priority]),
+                Ownership::CoOwned { priority } => hasher.write(&[1, priority]),
+                Ownership::Contended { ceiling } => hasher.write(&[2, ceiling]),
+            }
+        }
+
+        for locations in [
+            &self.shared_resource_locations,
+            &self.local_resource_locations,
+        ] {
+            hasher.write_len(locations.len());
+            for (name, location) in locations {
+                hasher.write_str(&name.to_string());
+                match location {
+                    Location::Owned { core } => {
+                        hasher.write(&[0]);
+                        match core {
+                            None => hasher.write(&[0]),
+                            Some(core) => hasher.write(&[1, *core]),
+                        }
+                    }
+                    Location::Shared { cores } => {
+                        hasher.write(&[1]);
+                        hasher.write_len(cores.len());
+                        // `BTreeSet` iterates in ascending order
+                        cores.iter().for_each(|core| hasher.write(&[*core]));
+                    }
+                }
+            }
+        }
+
+        for types in [&self.send_types, &self.sync_types] {
+            hasher.write_len(types.len());
+            for ty in types {
+                hasher.write_tokens(quote!(#ty));
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Priorities, in ascending order, that have at least one software task and therefore need a
+    /// dispatcher
+    pub fn used_dispatcher_priorities(&self) -> Vec<Priority> {
+        self.channels.keys().cloned().collect()
+    }
+
+    /// The number of messages that can be queued at the same time, across the dispatchers of all
+    /// priorities
+    ///
+    /// See `total_capacity`
+    pub fn total_spawn_slots(&self) -> usize {
+        total_capacity(&self.channels)
+    }
+
+    /// *Used* shared resources grouped by the core on which they are located, see
+    /// `Location::core`
+    ///
+    /// In single-core applications all of them are grouped under `None`, as are the resources
+    /// shared between cores
+    pub fn resources_by_core(&self) -> BTreeMap<Option<u8>, Vec<Resource>> {
+        let mut resources = BTreeMap::new();
+        for (name, location) in &self.shared_resource_locations {
+            resources
+                .entry(location.core())
+                .or_insert_with(Vec::new)
+                .push(name.clone());
+        }
+        resources
+    }
+
+    /// The differences between this analysis and the `other` one, taking this one as the old one
+    pub fn diff(&self, other: &Analysis) -> AnalysisDiff {
+        let added_tasks = other
+            .tasks
+            .iter()
+            .filter(|task| !self.tasks.contains(task))
+            .cloned()
+            .collect();
+        let removed_tasks = self
+            .tasks
+            .iter()
+            .filter(|task| !other.tasks.contains(task))
+            .cloned()
+            .collect();
+
+        let ownerships = self
+            .ownerships
+            .keys()
+            .chain(
+                other
+                    .ownerships
+                    .keys()
+                    .filter(|name| !self.ownerships.contains_key(*name)),
+            )
+            .filter_map(|name| {
+                let old = self.ownerships.get(name).cloned();
+                let new = other.ownerships.get(name).cloned();
+                if old == new {
+                    None
+                } else {
+                    Some((name.clone(), old, new))
+                }
+            })
+            .collect();
+
+        let capacities = self
+            .channels
+            .keys()
+            .chain(other.channels.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|priority| {
+                let old = self.channels.get(priority).map(|channel| channel.capacity);
+                let new = other.channels.get(priority).map(|channel| channel.capacity);
+                if old == new {
+                    None
+                } else {
+                    Some((*priority, old, new))
+                }
+            })
+            .collect();
+
+        AnalysisDiff {
+            added_tasks,
+            removed_tasks,
+            ownerships,
+            capacities,
+        }
+    }
+
+    /// A summary of each dispatcher, in ascending order of priority
+    pub fn dispatcher_summaries(&self) -> Vec<DispatcherSummary> {
+        self.channels
+            .iter()
+            .map(|(priority, channel)| DispatcherSummary {
+                priority: *priority,
+                capacity: channel.capacity,
+                tasks: channel.tasks.iter().cloned().collect(),
+            })
+            .collect()
+    }
+
+    /// Shared resources declared in `#[shared]` that are never accessed
+    pub fn dead_shared_resources(&self, app: &App) -> Vec<Resource> {
+        app.shared_resources
+            .keys()
+            .filter(|name| !self.shared_resource_locations.contains_key(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// Local resources declared in `#[local]` that are never accessed
+    pub fn dead_local_resources(&self, app: &App) -> Vec<Resource> {
+        app.local_resources
+            .keys()
+            .filter(|name| !self.local_resource_locations.contains_key(*name))
+            .cloned()
+            .collect()
+    }
 }
 
 /// All channels, keyed by dispatch priority
@@ -344,27 +1927,314 @@ pub type SharedResourceLocations = IndexMap<Resource, Location>;
 /// Location of all *used* local resources
 pub type LocalResourceLocations = IndexMap<Resource, Location>;
 
+/// The task that owns each local resource
+pub type LocalResourceOwners = IndexMap<Resource, Task>;
+
 /// Resource ownership
 pub type Ownerships = IndexMap<Resource, Ownership>;
 
+/// Priority ceiling of all *used* shared resources
+pub type Ceilings = IndexMap<Resource, u8>;
+
+/// *Used* shared resources annotated `#[lock_free]`
+pub type LockFreeResources = BTreeSet<Resource>;
+
+/// Priority inversion of contended resources
+pub type InversionWarnings = IndexMap<Resource, u8>;
+
+/// Documentation of resources
+pub type ResourceDocs = IndexMap<Resource, Vec<String>>;
+
+/// Resources initialized by `init`
+pub type LateResources = BTreeSet<Resource>;
+
+/// Highest lock ceiling of the hardware tasks that need to raise their priority
+pub type EntryCeilings = IndexMap<Task, u8>;
+
+/// Priorities masked by the lock of each contended shared resource
+pub type MaskLevels = IndexMap<Resource, BTreeSet<Priority>>;
+
+/// Mutability of all *used* shared resources
+pub type ResourceMutability = IndexMap<Resource, Mutability>;
+
+/// Shared resources that each task needs to lock
+pub type Locks = IndexMap<Task, Vec<Resource>>;
+
+/// Number of shared and exclusive accesses of all *used* shared resources
+pub type AccessStats = IndexMap<Resource, (usize, usize)>;
+
 /// These types must implement the `Send` trait
 pub type SendTypes = Set<Box<Type>>;
 
 /// These types must implement the `Sync` trait
 pub type SyncTypes = Set<Box<Type>>;
 
+/// Why some types must implement the `Send` or `Sync` traits
+pub type TypeReasons = IndexMap<Box<Type>, Vec<SendReason>>;
+
+/// Why a type must implement the `Send` (or `Sync`) trait
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SendReason {
+    /// The type of this shared resource, which tasks at different priorities access through
+    /// shared references
+    ///
+    /// Only found in `Analysis::sync_type_reasons`
+    SharedSync(#[cfg_attr(feature = "serde", serde(serialize_with = "ser::tokens"))] Resource),
+
+    /// The type of this resource, which `init` initializes and hands off to the task(s) that use
+    /// it
+    InitHandoff(#[cfg_attr(feature = "serde", serde(serialize_with = "ser::tokens"))] Resource),
+
+    /// The type of this shared resource, which is contended by tasks at different priorities
+    SharedAcrossPriorities(
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::tokens"))] Resource,
+    ),
+
+    /// The type of an input of this software task
+    TaskInput(#[cfg_attr(feature = "serde", serde(serialize_with = "ser::tokens"))] Task),
+}
+
+fn add_reason(reasons: &mut TypeReasons, ty: Box<Type>, reason: SendReason) {
+    let reasons = reasons.entry(ty).or_default();
+
+    if !reasons.contains(&reason) {
+        reasons.push(reason);
+    }
+}
+
+/// The software tasks that each task spawns
+pub type SpawnGraph = IndexMap<Task, BTreeSet<Task>>;
+
+/// Estimates the number of elements of `ty`: the length of arrays multiplied by the number of
+/// elements of their element type, the sum of the elements of the fields of tuples, and 1 for any
+/// other type
+///
+/// The estimate saturates at `usize::MAX`
+fn type_elements(ty: &Type) -> usize {
+    match ty {
+        Type::Array(array) => {
+            let len: usize = match &array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(len),
+                    ..
+                }) => len.base10_parse().unwrap_or(1),
+                _ => 1,
+            };
+
+            len.saturating_mul(type_elements(&array.elem))
+        }
+        Type::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .map(type_elements)
+            .fold(0, usize::saturating_add),
+        Type::Paren(syn::TypeParen { elem, .. }) | Type::Group(syn::TypeGroup { elem, .. }) => {
+            type_elements(elem)
+        }
+        _ => 1,
+    }
+}
+
+/// Whether `ty` contains a reference, or a lifetime argument or bound, that is not `'static`
+///
+/// The elided lifetimes of function pointers and `Fn` traits, and the ones introduced by
+/// `for<'a>`, are bound by them so they are not considered
+fn borrows_non_static(ty: &Type) -> bool {
+    type_borrows(ty, false, &[])
+}
+
+// `in_signature` is set inside the signature of function pointers and `Fn` traits; `bound` holds
+// the lifetimes introduced by the enclosing `for<..>`s
+fn type_borrows(ty: &Type, in_signature: bool, bound: &[Ident]) -> bool {
+    match ty {
+        Type::Reference(reference) => {
+            reference
+                .lifetime
+                .as_ref()
+                .map(|lifetime| lifetime_borrows(lifetime, in_signature, bound))
+                .unwrap_or(!in_signature)
+                || type_borrows(&reference.elem, in_signature, bound)
+        }
+        Type::Array(syn::TypeArray { elem, .. })
+        | Type::Group(syn::TypeGroup { elem, .. })
+        | Type::Paren(syn::TypeParen { elem, .. })
+        | Type::Ptr(syn::TypePtr { elem, .. })
+        | Type::Slice(syn::TypeSlice { elem, .. }) => type_borrows(elem, in_signature, bound),
+        Type::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .any(|elem| type_borrows(elem, in_signature, bound)),
+        Type::Path(path) => {
+            path.qself
+                .as_ref()
+                .map(|qself| type_borrows(&qself.ty, in_signature, bound))
+                .unwrap_or(false)
+                || path_borrows(&path.path, in_signature, bound)
+        }
+        Type::TraitObject(syn::TypeTraitObject { bounds, .. })
+        | Type::ImplTrait(syn::TypeImplTrait { bounds, .. }) => {
+            bounds.iter().any(|param_bound| match param_bound {
+                syn::TypeParamBound::Lifetime(lifetime) => {
+                    lifetime_borrows(lifetime, in_signature, bound)
+                }
+                syn::TypeParamBound::Trait(trait_bound) => {
+                    let bound = with_bound_lifetimes(bound, trait_bound.lifetimes.as_ref());
+                    path_borrows(&trait_bound.path, in_signature, &bound)
+                }
+            })
+        }
+        Type::BareFn(bare_fn) => {
+            let bound = with_bound_lifetimes(bound, bare_fn.lifetimes.as_ref());
+            bare_fn
+                .inputs
+                .iter()
+                .any(|input| type_borrows(&input.ty, true, &bound))
+                || return_type_borrows(&bare_fn.output, &bound)
+        }
+        _ => false,
+    }
+}
+
+fn lifetime_borrows(lifetime: &syn::Lifetime, in_signature: bool, bound: &[Ident]) -> bool {
+    lifetime.ident != "static"
+        && !(in_signature && lifetime.ident == "_")
+        && !bound.contains(&lifetime.ident)
+}
+
+fn path_borrows(path: &syn::Path, in_signature: bool, bound: &[Ident]) -> bool {
+    path.segments
+        .iter()
+        .any(|segment| match &segment.arguments {
+            syn::PathArguments::AngleBracketed(arguments) => {
+                arguments.args.iter().any(|argument| match argument {
+                    syn::GenericArgument::Lifetime(lifetime) => {
+                        lifetime_borrows(lifetime, in_signature, bound)
+                    }
+                    syn::GenericArgument::Type(ty) => type_borrows(ty, in_signature, bound),
+                    syn::GenericArgument::Binding(binding) => {
+                        type_borrows(&binding.ty, in_signature, bound)
+                    }
+                    _ => false,
+                })
+            }
+            // `Fn(&u8) -> &u8` and the like
+            syn::PathArguments::Parenthesized(arguments) => {
+                arguments
+                    .inputs
+                    .iter()
+                    .any(|input| type_borrows(input, true, bound))
+                    || return_type_borrows(&arguments.output, bound)
+            }
+            syn::PathArguments::None => false,
+        })
+}
+
+fn return_type_borrows(output: &syn::ReturnType, bound: &[Ident]) -> bool {
+    match output {
+        syn::ReturnType::Default => false,
+        syn::ReturnType::Type(_, ty) => type_borrows(ty, true, bound),
+    }
+}
+
+fn with_bound_lifetimes(bound: &[Ident], lifetimes: Option<&syn::BoundLifetimes>) -> Vec<Ident> {
+    bound
+        .iter()
+        .cloned()
+        .chain(
+            lifetimes
+                .into_iter()
+                .flat_map(|lifetimes| lifetimes.lifetimes.iter())
+                .map(|def| def.lifetime.ident.clone()),
+        )
+        .collect()
+}
+
+/// Collects the software tasks spawned, e.g. `foo::spawn(..)`, in the given `tokens`
+fn spawned_tasks(app: &App, tokens: TokenStream2, spawnees: &mut BTreeSet<Task>) {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+
+    for (i, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Group(group) => spawned_tasks(app, group.stream(), spawnees),
+
+            TokenTree::Ident(name) if app.software_tasks.contains_key(name) => {
+                if let [TokenTree::Punct(first), TokenTree::Punct(second), TokenTree::Ident(method), ..] =
+                    &tokens[i + 1..]
+                {
+                    if first.as_char() == ':'
+                        && second.as_char() == ':'
+                        && (method == "spawn" || method == "spawn_after" || method == "spawn_at")
+                    {
+                        spawnees.insert(name.clone());
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Priorities used by both hardware tasks and software task dispatchers
+pub type SharedPriorities = BTreeSet<Priority>;
+
 /// A channel used to send messages
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Channel {
     /// The channel capacity
     pub capacity: u8,
 
     /// Tasks that can be spawned on this channel
+    ///
+    /// These are iterated in lexical order of the task names, not in declaration order, so
+    /// backends can derive stable message discriminants from this order
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
     pub tasks: BTreeSet<Task>,
+
+    /// The types of the inputs of all the `tasks`, in the same order as the tasks
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::seq"))]
+    pub input_types: Vec<Box<Type>>,
+}
+
+/// Whether a shared resource needs a lock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CeilingInfo {
+    /// Not a *used* shared resource, e.g. a dead or a local resource
+    Unknown,
+
+    /// The resource is owned or co-owned so it's accessed without a lock
+    NoLock,
+
+    /// The resource is contended and locking it raises the priority to this ceiling
+    Lock(u8),
+}
+
+/// The kind of a resource
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResourceKind {
+    /// Declared in the `#[shared]` struct
+    Shared,
+
+    /// Declared in the `#[local]` struct or in the `local` argument of a task
+    Local,
+}
+
+/// How tasks access a shared resource
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Mutability {
+    /// Every task accesses the resource through a shared reference, `&self` accessors suffice
+    Shared,
+
+    /// At least one task accesses the resource exclusively, `&mut self` accessors are needed
+    Exclusive,
 }
 
 /// Resource ownership
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Ownership {
     /// Owned by a single task
     Owned {
@@ -403,11 +2273,277 @@ impl Ownership {
     pub fn is_owned(&self) -> bool {
         matches!(self, Ownership::Owned { .. })
     }
+
+    /// Whether this resource is contended
+    pub fn is_contended(&self) -> bool {
+        matches!(self, Ownership::Contended { .. })
+    }
+
+    /// The priority of the owner(s) of this resource or, if it's contended, its ceiling
+    pub fn priority(&self) -> u8 {
+        match *self {
+            Ownership::Owned { priority } | Ownership::CoOwned { priority } => priority,
+            Ownership::Contended { ceiling } => ceiling,
+        }
+    }
+}
+
+/// Formats the ownership as `owned(p=N)`, `coowned(p=N)` or `contended(ceil=N)`
+///
+/// Unlike the `Debug` output this format is stable
+impl fmt::Display for Ownership {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ownership::Owned { priority } => write!(f, "owned(p={})", priority),
+            Ownership::CoOwned { priority } => write!(f, "coowned(p={})", priority),
+            Ownership::Contended { ceiling } => write!(f, "contended(ceil={})", ceiling),
+        }
+    }
+}
+
+/// 64-bit FNV-1a hasher
+struct Fnv1a {
+    hash: u64,
+}
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a {
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= u64::from(*byte);
+            self.hash = self.hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // lengths are written as 64-bit little endian integers, so the hash doesn't depend on the
+    // target
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+
+    // each token is tagged with its kind; groups also write their delimiter and are closed by
+    // their length
+    fn write_tokens(&mut self, tokens: TokenStream2) {
+        let mut len = 0;
+        for tt in tokens {
+            match tt {
+                TokenTree::Group(group) => {
+                    let delimiter = match group.delimiter() {
+                        Delimiter::Parenthesis => 0,
+                        Delimiter::Brace => 1,
+                        Delimiter::Bracket => 2,
+                        Delimiter::None => 3,
+                    };
+                    self.write(&[0, delimiter]);
+                    self.write_tokens(group.stream());
+                }
+                TokenTree::Ident(ident) => {
+                    self.write(&[1]);
+                    self.write_str(&ident.to_string());
+                }
+                TokenTree::Punct(punct) => {
+                    let spacing = match punct.spacing() {
+                        Spacing::Alone => 0,
+                        Spacing::Joint => 1,
+                    };
+                    self.write(&[2, spacing]);
+                    self.write(&u32::from(punct.as_char()).to_le_bytes());
+                }
+                TokenTree::Literal(literal) => {
+                    self.write(&[3]);
+                    self.write_str(&literal.to_string());
+                }
+            }
+            len += 1;
+        }
+        self.write_len(len);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
 }
 
 /// Resource location
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Location {
-    /// resource that is owned
-    Owned,
+    /// resource that is owned by a single core
+    Owned {
+        /// Core on which this resource is located; `None` in single-core applications
+        core: Option<u8>,
+    },
+
+    /// resource that is shared between cores
+    Shared {
+        /// Cores that access this resource
+        cores: BTreeSet<u8>,
+    },
+}
+
+impl Location {
+    /// The core on which this resource is located
+    ///
+    /// Returns `None` in single-core applications and for resources shared between cores
+    pub fn core(&self) -> Option<u8> {
+        match self {
+            Location::Owned { core } => *core,
+            Location::Shared { .. } => None,
+        }
+    }
+
+    /// The location of a resource accessed from tasks pinned to the given `cores`
+    fn from_cores(cores: BTreeSet<u8>) -> Location {
+        if cores.len() > 1 {
+            Location::Shared { cores }
+        } else {
+            Location::Owned {
+                core: cores.into_iter().next(),
+            }
+        }
+    }
+}
+
+/// Serialization of syntax nodes, like `Ident` and `Type`, as strings
+#[cfg(feature = "serde")]
+mod ser {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use quote::ToTokens;
+    use serde::{Serialize, Serializer};
+
+    pub fn tokens<T, S>(node: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ToTokens,
+        S: Serializer,
+    {
+        serializer.serialize_str(&node.to_token_stream().to_string())
+    }
+
+    pub fn seq<C, T, S>(nodes: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = &'a T>,
+        T: ToTokens + 'static,
+        S: Serializer,
+    {
+        serializer.collect_seq(
+            nodes
+                .into_iter()
+                .map(|node| node.to_token_stream().to_string()),
+        )
+    }
+
+    pub fn map<C, K, V, S>(map: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = (&'a K, &'a V)>,
+        K: ToTokens + 'static,
+        V: Serialize + 'static,
+        S: Serializer,
+    {
+        serializer.collect_map(
+            map.into_iter()
+                .map(|(key, value)| (key.to_token_stream().to_string(), value)),
+        )
+    }
+
+    pub fn messages<S>(errors: &[syn::Error], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(errors.iter().map(|error| error.to_string()))
+    }
+
+    pub fn pairs<A, B, S>(pairs: &[(A, B)], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        A: ToTokens,
+        B: ToTokens,
+        S: Serializer,
+    {
+        serializer.collect_seq(pairs.iter().map(|(a, b)| {
+            (
+                a.to_token_stream().to_string(),
+                b.to_token_stream().to_string(),
+            )
+        }))
+    }
+
+    pub fn map_of_tokens<C, K, V, S>(map: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = (&'a K, &'a V)>,
+        K: ToTokens + 'static,
+        V: ToTokens + 'static,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(key, value)| {
+            (
+                key.to_token_stream().to_string(),
+                value.to_token_stream().to_string(),
+            )
+        }))
+    }
+
+    pub fn map_of_sets<C, K, T, S>(map: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = (&'a K, &'a BTreeSet<T>)>,
+        K: ToTokens + 'static,
+        T: ToTokens + 'static,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(key, nodes)| {
+            (
+                key.to_token_stream().to_string(),
+                nodes
+                    .iter()
+                    .map(|node| node.to_token_stream().to_string())
+                    .collect::<Vec<_>>(),
+            )
+        }))
+    }
+
+    pub fn sets_by_priority<T, S>(
+        map: &BTreeMap<u8, BTreeSet<T>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ToTokens,
+        S: Serializer,
+    {
+        serializer.collect_map(map.iter().map(|(priority, nodes)| {
+            (
+                priority,
+                nodes
+                    .iter()
+                    .map(|node| node.to_token_stream().to_string())
+                    .collect::<Vec<_>>(),
+            )
+        }))
+    }
+
+    pub fn map_of_seqs<C, K, T, S>(map: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = (&'a K, &'a Vec<T>)>,
+        K: ToTokens + 'static,
+        T: ToTokens + 'static,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(key, nodes)| {
+            (
+                key.to_token_stream().to_string(),
+                nodes
+                    .iter()
+                    .map(|node| node.to_token_stream().to_string())
+                    .collect::<Vec<_>>(),
+            )
+        }))
+    }
 }
@@ -150,9 +150,13 @@ impl Default for IdleArgs {
 
 /// Shared resource properties
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct SharedResourceProperties {
     /// A lock free (exclusive resource)
     pub lock_free: bool,
+
+    /// Accessed by exactly one task, see `Analysis::single_task_resources`
+    pub single_task: bool,
 }
 
 /// A shared resource, defined in `#[shared]`
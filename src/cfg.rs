@@ -0,0 +1,82 @@
+use syn::{Attribute, Lit, Meta, NestedMeta};
+
+use crate::{ast::App, Settings};
+
+/// Removes the tasks and resources whose `#[cfg]` attributes don't hold for
+/// `Settings::active_cfgs`
+pub fn app(app: &mut App, settings: &Settings) {
+    let active = if let Some(active) = &settings.active_cfgs {
+        active.iter().map(|cfg| normalize(cfg)).collect::<Vec<_>>()
+    } else {
+        return;
+    };
+
+    app.hardware_tasks
+        .retain(|_, task| is_active(&task.cfgs, &active));
+    app.software_tasks
+        .retain(|_, task| is_active(&task.cfgs, &active));
+    app.shared_resources
+        .retain(|_, res| is_active(&res.cfgs, &active));
+    app.local_resources
+        .retain(|_, res| is_active(&res.cfgs, &active));
+}
+
+// Removes the whitespace around the `=` of `name = "value"`, but not the one in the value
+fn normalize(cfg: &str) -> String {
+    match cfg.find('=') {
+        Some(pos) => format!("{}={}", cfg[..pos].trim(), cfg[pos + 1..].trim()),
+        None => cfg.trim().to_string(),
+    }
+}
+
+// Predicates that can't be parsed, or that can't be told to hold or not, leave the item in place
+fn is_active(cfgs: &[Attribute], active: &[String]) -> bool {
+    cfgs.iter().all(|attr| match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.nested.len() == 1 => {
+            holds(&list.nested[0], active) != Some(false)
+        }
+        _ => true,
+    })
+}
+
+// `None` if it can't be told whether `predicate` holds, e.g. `target_pointer_width = 64`
+fn holds(predicate: &NestedMeta, active: &[String]) -> Option<bool> {
+    let meta = if let NestedMeta::Meta(meta) = predicate {
+        meta
+    } else {
+        return None;
+    };
+
+    let ident = meta.path().get_ident()?.to_string();
+
+    match meta {
+        Meta::Path(_) => Some(active.contains(&ident)),
+
+        Meta::NameValue(nv) => match &nv.lit {
+            Lit::Str(value) => Some(active.contains(&format!("{}={:?}", ident, value.value()))),
+            _ => None,
+        },
+
+        Meta::List(list) => match &*ident {
+            // `false` wins over unknown predicates in `all`, and `true` in `any`
+            "all" | "any" => {
+                let decisive = Some(ident == "any");
+                let results = list
+                    .nested
+                    .iter()
+                    .map(|p| holds(p, active))
+                    .collect::<Vec<_>>();
+
+                if results.contains(&decisive) {
+                    decisive
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    decisive.map(|decisive| !decisive)
+                }
+            }
+            "not" if list.nested.len() == 1 => holds(&list.nested[0], active).map(|holds| !holds),
+            _ => None,
+        },
+    }
+}
@@ -8,7 +8,7 @@ pub fn app(app: &App) -> parse::Result<()> {
     // Check that all referenced resources have been declared
     // Check that resources are NOT `Exclusive`-ly shared
     let mut owners = HashSet::new();
-    for (_, name, access) in app.shared_resource_accesses() {
+    for (_, _, name, access) in app.shared_resource_accesses() {
         if app.shared_resources.get(name).is_none() {
             return Err(parse::Error::new(
                 name.span(),
@@ -33,7 +33,7 @@ pub fn app(app: &App) -> parse::Result<()> {
     // Check that no resource has both types of access (`Exclusive` & `Shared`)
     let exclusive_accesses = app
         .shared_resource_accesses()
-        .filter_map(|(priority, name, access)| {
+        .filter_map(|(_, priority, name, access)| {
             if priority.is_some() && access.is_exclusive() {
                 Some(name)
             } else {
@@ -41,7 +41,7 @@ pub fn app(app: &App) -> parse::Result<()> {
             }
         })
         .collect::<HashSet<_>>();
-    for (_, name, access) in app.shared_resource_accesses() {
+    for (_, _, name, access) in app.shared_resource_accesses() {
         if access.is_shared() && exclusive_accesses.contains(name) {
             return Err(parse::Error::new(
                 name.span(),
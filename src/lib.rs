@@ -20,6 +20,7 @@ use crate::ast::App;
 mod accessors;
 pub mod analyze;
 pub mod ast;
+mod cfg;
 mod check;
 mod optimize;
 mod parse;
@@ -133,6 +134,31 @@ pub struct Settings {
     pub parse_extern_interrupt: bool,
     /// Whether to "compress" priorities or not
     pub optimize_priorities: bool,
+    /// Whether to reject hardware tasks that run at the priority of a software task dispatcher
+    pub error_on_shared_priorities: bool,
+    /// Whether to reject shared and local resources that are never accessed
+    pub error_on_dead_resources: bool,
+    /// Whether to reject shared resources accessed by several tasks at the same priority
+    pub forbid_implicit_coownership: bool,
+    /// Warn about contended resources whose ceiling is more than this many levels above the
+    /// lowest priority that accesses them
+    pub priority_inversion_threshold: Option<u8>,
+    /// Warn about software task inputs that have more elements than this, see
+    /// `Analysis::large_inputs`
+    pub large_input_threshold: Option<usize>,
+    /// The capacity of the software tasks that don't specify one, 1 if unset
+    pub default_capacity: Option<u8>,
+    /// The configuration options that are set, in the syntax of `rustc --cfg`, e.g. `test` or
+    /// `feature="foo"`
+    ///
+    /// If set, the tasks and resources whose `#[cfg]` attributes don't hold are removed before
+    /// the application is checked and analyzed. Items whose predicates can't be evaluated, e.g.
+    /// `target_pointer_width = 64`, are kept
+    pub active_cfgs: Option<Vec<String>>,
+    /// The highest priority supported by the target, if any
+    pub max_priority: Option<u8>,
+    /// The width, in bits, of the index of the dispatcher queues, if the backend limits it
+    pub queue_index_bits: Option<u8>,
 }
 
 /// Parses the input of the `#[app]` attribute
@@ -151,11 +177,12 @@ pub fn parse2(
     settings: Settings,
 ) -> Result<(P<ast::App>, P<analyze::Analysis>), syn::parse::Error> {
     let mut app = parse::app(args, input, &settings)?;
+    cfg::app(&mut app, &settings);
     check::app(&app)?;
     optimize::app(&mut app, &settings);
 
-    match analyze::app(&app) {
-        Err(e) => Err(e),
+    match analyze::app(&app, &settings) {
+        Err(e) => Err(e.into()),
         // If no errors, return the app and analysis results
         Ok(analysis) => Ok((P::new(app), P::new(analysis))),
     }
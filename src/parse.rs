@@ -113,14 +113,11 @@ fn init_args(tokens: TokenStream2) -> parse::Result<InitArgs> {
 
         if let Some(locals) = &local_resources {
             for (ident, task_local) in locals {
-                match task_local {
-                    TaskLocal::External => {
-                        return Err(parse::Error::new(
-                            ident.span(),
-                            "only declared local resources are allowed in init",
-                        ));
-                    }
-                    _ => {}
+                if let TaskLocal::External = task_local {
+                    return Err(parse::Error::new(
+                        ident.span(),
+                        "only declared local resources are allowed in init",
+                    ));
                 }
             }
         }
@@ -176,6 +173,18 @@ fn idle_args(tokens: TokenStream2) -> parse::Result<IdleArgs> {
                     local_resources = Some(util::parse_local_resources(&content)?);
                 }
 
+                "priority" => {
+                    // #lit
+                    let lit: LitInt = content.parse()?;
+
+                    if lit.base10_parse::<u8>().ok() != Some(0) {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "`idle` always runs at priority 0",
+                        ));
+                    }
+                }
+
                 _ => {
                     return Err(parse::Error::new(ident.span(), "unexpected argument"));
                 }
@@ -203,7 +212,10 @@ fn task_args(
 ) -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
     (|input: ParseStream<'_>| -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
         if input.is_empty() {
-            return Ok(Either::Right(SoftwareTaskArgs::default()));
+            return Ok(Either::Right(SoftwareTaskArgs {
+                capacity: settings.default_capacity.unwrap_or(1),
+                ..SoftwareTaskArgs::default()
+            }));
         }
 
         let mut binds = None;
@@ -366,7 +378,7 @@ fn task_args(
             })
         } else {
             Either::Right(SoftwareTaskArgs {
-                capacity: capacity.unwrap_or(1),
+                capacity: capacity.or(settings.default_capacity).unwrap_or(1),
                 priority,
                 shared_resources,
                 local_resources,
@@ -468,7 +468,7 @@ impl App {
                             ));
                         }
 
-                        check_monotonic(&*type_item.ty)?;
+                        check_monotonic(&type_item.ty)?;
 
                         let args = MonotonicArgs::parse(type_item.attrs.remove(pos).tokens)?;
 
@@ -40,7 +40,7 @@ impl HardwareTask {
 
         Err(parse::Error::new(
             span,
-            &format!(
+            format!(
                 "this task handler must have type signature `fn({}::Context)`",
                 name
             ),
@@ -86,7 +86,7 @@ impl HardwareTask {
 
         Err(parse::Error::new(
             span,
-            &format!(
+            format!(
                 "this task handler must have type signature `fn({}::Context)`",
                 name
             ),
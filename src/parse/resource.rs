@@ -18,12 +18,16 @@ impl SharedResource {
         let (cfgs, mut attrs) = util::extract_cfgs(item.attrs.clone());
 
         let lock_free = util::extract_lock_free(&mut attrs)?;
+        let single_task = util::extract_single_task(&mut attrs)?;
 
         Ok(SharedResource {
             cfgs,
             attrs,
             ty: Box::new(item.ty.clone()),
-            properties: SharedResourceProperties { lock_free },
+            properties: SharedResourceProperties {
+                lock_free,
+                single_task,
+            },
         })
     }
 }
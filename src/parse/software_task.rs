@@ -32,7 +32,7 @@ impl SoftwareTask {
 
         Err(parse::Error::new(
             span,
-            &format!(
+            format!(
                 "this task handler must have type signature `fn({}::Context, ..)`",
                 name
             ),
@@ -70,7 +70,7 @@ impl SoftwareTask {
 
         Err(parse::Error::new(
             span,
-            &format!(
+            format!(
                 "this task handler must have type signature `fn({}::Context, ..)`",
                 name
             ),
@@ -82,6 +82,15 @@ pub fn extract_lock_free(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
     }
 }
 
+pub fn extract_single_task(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
+    if let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "single_task")) {
+        attrs.remove(pos);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
 pub fn parse_shared_resources(content: ParseStream<'_>) -> parse::Result<SharedResources> {
     let inner;
     bracketed!(inner in content);
@@ -101,6 +110,14 @@ pub fn parse_shared_resources(content: ParseStream<'_>) -> parse::Result<SharedR
                 _ => return err,
             },
 
+            // e.g. `foo as bar`
+            Expr::Cast(_) => {
+                return Err(parse::Error::new_spanned(
+                    e,
+                    "resources can't be renamed; use the name of the field in `#[shared]`",
+                ))
+            }
+
             _ => return err,
         };
 
@@ -282,13 +299,11 @@ pub fn type_is_init_return(ty: &ReturnType, name: &str) -> Result<(Ident, Ident)
                 //
                 // We check the length and the last one here, analysis checks that the user
                 // provided structs are correct.
-                if t.elems.len() == 3 {
-                    if type_is_path(&t.elems[2], &[name, "Monotonics"]) {
-                        return Ok((
-                            extract_init_resource_name_ident(t.elems[0].clone())?,
-                            extract_init_resource_name_ident(t.elems[1].clone())?,
-                        ));
-                    }
+                if t.elems.len() == 3 && type_is_path(&t.elems[2], &[name, "Monotonics"]) {
+                    return Ok((
+                        extract_init_resource_name_ident(t.elems[0].clone())?,
+                        extract_init_resource_name_ident(t.elems[1].clone())?,
+                    ));
                 }
 
                 Err(())
@@ -1,4 +1,11 @@
-use crate::{analyze::Ownership, Settings};
+use crate::{
+    analyze::{
+        self, CeilingInfo, Location, Mutability, Ownership, ResourceKind, SendReason, TaskKind,
+    },
+    ast::Access,
+    Settings,
+};
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
 #[test]
@@ -432,3 +439,3840 @@ fn late_resources() {
     let late = &app.shared_resources;
     assert_eq!(late.len(), 1);
 }
+
+#[test]
+fn shared_resource_ceilings() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: i32,
+                    b: i32,
+                    c: i32,
+                    d: i32,
+                    dead: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [d])]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+
+                #[task(shared = [a, b, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [b])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [c])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let ceiling = |name: &str| {
+        analysis
+            .ceilings
+            .iter()
+            .find(|(res, _)| *res == name)
+            .map(|(_, ceiling)| *ceiling)
+    };
+    assert_eq!(ceiling("a"), Some(1));
+    assert_eq!(ceiling("b"), Some(1));
+    assert_eq!(ceiling("c"), Some(3));
+    assert_eq!(ceiling("d"), Some(0));
+    assert_eq!(ceiling("dead"), None);
+}
+
+#[test]
+fn max_priority() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.max_priority(), 0);
+
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 2)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART0, priority = 4)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(analysis.max_priority(), 4);
+}
+
+#[test]
+fn lock_free_errors_reported_once() {
+    // every use of the violating resource is reported exactly once
+    let err = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    x: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [x])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2, shared = [x])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+
+    // one error on the declaration and one for each of the three uses
+    assert_eq!(err.into_iter().count(), 4);
+}
+
+#[test]
+fn single_core_locations() {
+    // without multi-core support all resources are owned by the default core
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                }
+
+                #[local]
+                struct Local {
+                    y: i32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x], local = [y])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let (res, location) = analysis.shared_resource_locations.iter().next().unwrap();
+    assert_eq!(res.to_string(), "x");
+    assert_eq!(*location, Location::Owned { core: None });
+
+    let (res, location) = analysis.local_resource_locations.iter().next().unwrap();
+    assert_eq!(res.to_string(), "y");
+    assert_eq!(*location, Location::Owned { core: None });
+}
+
+#[test]
+fn dead_resources() {
+    let (app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    used: i32,
+                    unused: i32,
+                }
+
+                #[local]
+                struct Local {
+                    a: i32,
+                    b: i32,
+                }
+
+                #[init(local = [c: i32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [used], local = [a])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let dead = analysis.dead_shared_resources(&app);
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].to_string(), "unused");
+
+    let dead = analysis.dead_local_resources(&app);
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].to_string(), "b");
+}
+
+#[test]
+fn lock_free_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    x: i32,
+                    #[lock_free]
+                    unused: i32,
+                    y: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x, y])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [x, y])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.lock_free.len(), 1);
+    assert_eq!(analysis.lock_free.iter().next().unwrap().to_string(), "x");
+
+    // tasks at the same priority never preempt each other so both can access `x` exclusively
+    let x = quote::format_ident!("x");
+    assert_eq!(analysis.access_stats[&x], (0, 2));
+    assert_eq!(analysis.ownerships[&x], Ownership::CoOwned { priority: 1 });
+}
+
+#[test]
+fn locks() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                    y: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x, y])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let locks = |name: &str| {
+        analysis
+            .locks
+            .iter()
+            .find(|(task, _)| *task == name)
+            .map(|(_, resources)| resources.iter().map(|r| r.to_string()).collect::<Vec<_>>())
+    };
+    assert_eq!(locks("init"), Some(vec![]));
+    assert_eq!(locks("foo"), Some(vec!["x".to_string()]));
+    assert_eq!(locks("bar"), Some(vec![]));
+}
+
+#[test]
+fn shared_priorities() {
+    let input = quote!(
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(priority = 2)]
+            fn foo(_: foo::Context) {}
+
+            #[task(binds = UART0, priority = 2)]
+            fn bar(_: bar::Context) {}
+
+            #[task(binds = UART1, priority = 3)]
+            fn baz(_: baz::Context) {}
+        }
+    );
+
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        input.clone(),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        analysis.shared_priorities.iter().collect::<Vec<_>>(),
+        vec![&2]
+    );
+
+    let result = crate::parse2(
+        quote!(),
+        input,
+        Settings {
+            parse_binds: true,
+            error_on_shared_priorities: true,
+            ..Settings::default()
+        },
+    );
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn ownership_priority() {
+    assert_eq!(Ownership::Owned { priority: 1 }.priority(), 1);
+    assert_eq!(Ownership::CoOwned { priority: 2 }.priority(), 2);
+    assert_eq!(Ownership::Contended { ceiling: 3 }.priority(), 3);
+
+    assert!(!Ownership::Owned { priority: 1 }.is_contended());
+    assert!(!Ownership::CoOwned { priority: 2 }.is_contended());
+    assert!(Ownership::Contended { ceiling: 3 }.is_contended());
+}
+
+#[test]
+fn channel_task_order() {
+    // tasks are iterated in lexical order regardless of their declaration order
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn zeta(_: zeta::Context) {}
+
+                #[task]
+                fn alpha(_: alpha::Context) {}
+
+                #[task]
+                fn mid(_: mid::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let channel = &analysis.channels[&1];
+    let tasks = channel
+        .tasks
+        .iter()
+        .map(|task| task.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(tasks, ["alpha", "mid", "zeta"]);
+}
+
+#[test]
+fn tasks_with_kind() {
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+
+                #[task(priority = 2)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART0, priority = 3)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let tasks = app
+        .tasks_with_kind()
+        .into_iter()
+        .map(|(name, kind, priority)| (name.to_string(), kind, priority))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tasks,
+        [
+            ("idle".to_string(), TaskKind::Idle, 0),
+            ("foo".to_string(), TaskKind::Software, 2),
+            ("bar".to_string(), TaskKind::Hardware, 3),
+        ]
+    );
+}
+
+#[test]
+fn idle_priority_zero() {
+    // `idle` may spell out the priority it always runs at
+    crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(priority = 0)]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn contention_graph() {
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                    y: i32,
+                    unused: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [y])]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+
+                #[task(shared = [x, y])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let graph = crate::analyze::contention_graph(&app)
+        .into_iter()
+        .map(|(name, tasks)| {
+            let tasks = tasks
+                .into_iter()
+                .map(|(task, priority)| (task.to_string(), priority))
+                .collect::<Vec<_>>();
+
+            (name.to_string(), tasks)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        graph,
+        [
+            (
+                "x".to_string(),
+                vec![("foo".to_string(), 1), ("bar".to_string(), 2)]
+            ),
+            (
+                "y".to_string(),
+                vec![("idle".to_string(), 0), ("foo".to_string(), 1)]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn max_priority_setting() {
+    let input = quote!(
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(priority = 8)]
+            fn foo(_: foo::Context) {}
+        }
+    );
+
+    assert!(crate::parse2(quote!(), input.clone(), Settings::default()).is_ok());
+
+    let err = crate::parse2(
+        quote!(),
+        input,
+        Settings {
+            max_priority: Some(7),
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Task \"foo\" has priority 8, which exceeds the maximum priority of 7"
+    );
+}
+
+#[test]
+fn resource_accessors() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                    y: i32,
+                    unused: i32,
+                }
+
+                #[local]
+                struct Local {
+                    z: i32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x, &y], local = [z, w: u8 = 0])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x, &y], local = [w: u8 = 1])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let accessors = |name: &str| {
+        analysis
+            .resource_accessors(&quote::format_ident!("{}", name))
+            .into_iter()
+            .map(|(task, access)| (task.to_string(), access))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        accessors("x"),
+        [
+            ("foo".to_string(), Access::Exclusive),
+            ("bar".to_string(), Access::Exclusive)
+        ]
+    );
+    assert_eq!(
+        accessors("y"),
+        [
+            ("foo".to_string(), Access::Shared),
+            ("bar".to_string(), Access::Shared)
+        ]
+    );
+    assert_eq!(accessors("z"), [("foo".to_string(), Access::Exclusive)]);
+    // two different resources that happen to share a name
+    assert_eq!(accessors("w"), []);
+    assert_eq!(accessors("unused"), []);
+    assert_eq!(accessors("unknown"), []);
+}
+
+#[test]
+fn demotable_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    owned: i32,
+                    coowned: i32,
+                    contended: i32,
+                    idle: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [idle])]
+                fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+
+                #[task(shared = [owned, coowned, contended])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [coowned])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2, shared = [contended])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.demotable_resources.len(), 1);
+    assert_eq!(analysis.demotable_resources[0].to_string(), "owned");
+}
+
+#[test]
+fn access_stats() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                    y: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x, &y])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x, &y])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [&y])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let stats = analysis
+        .access_stats
+        .iter()
+        .map(|(name, stats)| (name.to_string(), *stats))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stats,
+        [("x".to_string(), (0, 2)), ("y".to_string(), (3, 0))]
+    );
+}
+
+#[test]
+fn send_type_reasons() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: X,
+                    b: Y,
+                }
+
+                #[local]
+                struct Local {
+                    c: X,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, &b], local = [c])]
+                fn foo(_: foo::Context, _: Y) {}
+
+                #[task(priority = 2, shared = [&b])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let reasons = analysis
+        .send_type_reasons
+        .iter()
+        .map(|(ty, reasons)| (quote!(#ty).to_string(), reasons.clone()))
+        .collect::<Vec<_>>();
+    let ident = |name: &str| quote::format_ident!("{}", name);
+    assert_eq!(
+        reasons,
+        [
+            (
+                "X".to_string(),
+                vec![
+                    SendReason::InitHandoff(ident("a")),
+                    SendReason::InitHandoff(ident("c"))
+                ]
+            ),
+            (
+                "Y".to_string(),
+                vec![
+                    SendReason::SharedAcrossPriorities(ident("b")),
+                    SendReason::TaskInput(ident("foo"))
+                ]
+            ),
+        ]
+    );
+
+    let (ty, reasons) = analysis.sync_type_reasons.iter().next().unwrap();
+    assert_eq!(quote!(#ty).to_string(), "Y");
+    assert_eq!(*reasons, [SendReason::SharedSync(ident("b"))]);
+}
+
+#[test]
+fn used_dispatcher_priorities() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3)]
+                fn a(_: a::Context) {}
+
+                #[task]
+                fn b(_: b::Context) {}
+
+                #[task(priority = 3)]
+                fn c(_: c::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.used_dispatcher_priorities(), [1, 3]);
+}
+
+#[test]
+fn check_dispatchers() {
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3)]
+                fn a(_: a::Context) {}
+
+                #[task]
+                fn b(_: b::Context) {}
+
+                #[task(priority = 3)]
+                fn c(_: c::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert!(crate::analyze::check_dispatchers(&app, 2).is_ok());
+    assert_eq!(
+        crate::analyze::check_dispatchers(&app, 1)
+            .unwrap_err()
+            .to_string(),
+        "software tasks need 2 dispatchers but only 1 are available"
+    );
+}
+
+#[test]
+fn late_resources_analysis() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {
+                    b: u32,
+                }
+
+                #[init(local = [c: u32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a], local = [b, d: u32 = 0])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let late = analysis
+        .late_resources
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(late, ["a", "b"]);
+}
+
+#[test]
+fn implicitly_lock_free() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // co-owned
+                    a: u32,
+                    // owned
+                    b: u32,
+                    // contended
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2, shared = [c])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .implicitly_lock_free
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(resources, ["a"]);
+}
+
+#[test]
+fn error_on_dead_resources() {
+    let app = quote!(
+        mod app {
+            #[shared]
+            struct Shared {
+                a: u32,
+                b: u32,
+            }
+
+            #[local]
+            struct Local {
+                c: u32,
+                d: u32,
+            }
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(shared = [a], local = [c])]
+            fn foo(_: foo::Context) {}
+        }
+    );
+
+    assert!(crate::parse2(quote!(), app.clone(), Settings::default()).is_ok());
+
+    let err = crate::parse2(
+        quote!(),
+        app,
+        Settings {
+            error_on_dead_resources: true,
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+    let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Shared resource \"b\" is never used",
+            "Local resource \"d\" is never used"
+        ]
+    );
+}
+
+#[test]
+fn task_resources() {
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {
+                    b: u32,
+                }
+
+                #[init(local = [c: u32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [a])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 2, shared = [a], local = [b, d: u32 = 0])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let tasks = app
+        .task_resources()
+        .into_iter()
+        .map(|task| {
+            (
+                task.name.to_string(),
+                task.shared
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+                task.local
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+                task.priority,
+            )
+        })
+        .collect::<Vec<_>>();
+    let strings = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tasks,
+        [
+            ("init".to_string(), vec![], strings(&["c"]), 0),
+            ("idle".to_string(), strings(&["a"]), vec![], 0),
+            ("foo".to_string(), strings(&["a"]), strings(&["b", "d"]), 2),
+        ]
+    );
+}
+
+#[test]
+fn ownership_display() {
+    assert_eq!(Ownership::Owned { priority: 3 }.to_string(), "owned(p=3)");
+    assert_eq!(
+        Ownership::CoOwned { priority: 2 }.to_string(),
+        "coowned(p=2)"
+    );
+    assert_eq!(
+        Ownership::Contended { ceiling: 5 }.to_string(),
+        "contended(ceil=5)"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_analysis() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a])]
+                fn foo(_: foo::Context, _: u8) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let json = serde_json::to_value(&*analysis).unwrap();
+    assert_eq!(json["tasks"], serde_json::json!(["init", "foo", "bar"]));
+    assert_eq!(
+        json["ownerships"],
+        serde_json::json!({ "a": { "Contended": { "ceiling": 2 } } })
+    );
+    assert_eq!(
+        json["channels"]["1"],
+        serde_json::json!({ "capacity": 1, "tasks": ["foo"], "input_types": ["u8"] })
+    );
+    assert_eq!(
+        json["send_type_reasons"]["u8"],
+        serde_json::json!([{ "TaskInput": "foo" }])
+    );
+    assert!(json.get("max_priority").is_none());
+    assert!(json.get("task_ceilings").is_none());
+}
+
+#[test]
+fn no_shared_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {
+                    a: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(local = [a])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 2, local = [b: u32 = 0])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.ownerships.is_empty());
+    assert!(analysis.sync_types.is_empty());
+    assert!(analysis.shared_resource_locations.is_empty());
+    assert!(analysis.ceilings.is_empty());
+    assert!(analysis.access_stats.is_empty());
+    assert_eq!(analysis.local_resource_locations.len(), 2);
+    assert_eq!(analysis.max_priority(), 2);
+}
+
+#[test]
+fn spawn_graph() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn(1).unwrap();
+                }
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {
+                    loop {
+                        app::bar::spawn_after(1.secs()).ok();
+                    }
+                }
+
+                #[task]
+                fn foo(_: foo::Context, _: u32) {
+                    if true {
+                        bar::spawn().ok();
+                        baz::spawn_at(0).ok();
+                    }
+                }
+
+                #[task]
+                fn bar(_: bar::Context) {
+                    // not a spawn
+                    let _ = foo::Context;
+                }
+
+                #[task]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let graph = analysis
+        .spawn_graph
+        .iter()
+        .map(|(task, spawnees)| {
+            (
+                task.to_string(),
+                spawnees
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let strings = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        graph,
+        [
+            ("init".to_string(), strings(&["foo"])),
+            ("idle".to_string(), strings(&["bar"])),
+            ("foo".to_string(), strings(&["bar", "baz"])),
+            ("bar".to_string(), vec![]),
+            ("baz".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn queue_index_bits() {
+    let app = quote!(
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(capacity = 200)]
+            fn a(_: a::Context) {}
+
+            #[task(capacity = 55)]
+            fn b(_: b::Context) {}
+
+            #[task(priority = 2, capacity = 254)]
+            fn c(_: c::Context) {}
+        }
+    );
+
+    // 200 + 55 + 1 doesn't fit in a `u8`, 254 + 1 does
+    let err = crate::parse2(
+        quote!(),
+        app.clone(),
+        Settings {
+            queue_index_bits: Some(8),
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+    let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "The dispatcher queue at priority 1 (tasks: a, b) has a capacity of 255, which doesn't fit a 8-bit queue index",
+            "The dispatcher queue at priority 1 (tasks: a, b) has a capacity of 255, which doesn't fit a 8-bit queue index",
+        ]
+    );
+
+    assert!(crate::parse2(quote!(), app.clone(), Settings::default()).is_ok());
+    assert!(crate::parse2(
+        quote!(),
+        app,
+        Settings {
+            queue_index_bits: Some(16),
+            ..Settings::default()
+        },
+    )
+    .is_ok());
+}
+
+#[test]
+fn is_empty() {
+    let parse = |tasks| {
+        crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {
+                        a: u32,
+                    }
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #tasks
+                }
+            ),
+            Settings::default(),
+        )
+        .unwrap()
+        .1
+    };
+
+    assert!(crate::analyze::Analysis::empty().is_empty());
+    assert!(parse(quote!()).is_empty());
+    assert!(!parse(quote!(
+        #[idle]
+        fn idle(_: idle::Context) -> ! {}
+    ))
+    .is_empty());
+    assert!(!parse(quote!(
+        #[task]
+        fn foo(_: foo::Context) {}
+    ))
+    .is_empty());
+}
+
+#[test]
+fn idle_contended() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // idle and one task
+                    a: u32,
+                    // idle and two tasks
+                    b: u32,
+                    // two tasks
+                    c: u32,
+                    // idle only
+                    d: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [a, b, d])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [a, b, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [b, c])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .idle_contended
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(resources, ["a"]);
+    assert_eq!(
+        analysis.ownerships[&quote::format_ident!("a")],
+        Ownership::Contended { ceiling: 1 }
+    );
+}
+
+#[test]
+fn resources_by_priority_lock() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // ceiling 2
+                    a: u32,
+                    // ceiling 3
+                    b: u32,
+                    // owned
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [a])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [b, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a, b])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let locks = analysis
+        .resources_by_priority_lock
+        .iter()
+        .map(|(priority, resources)| {
+            (
+                *priority,
+                resources
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let strings = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        locks,
+        [
+            (0, strings(&["a", "b"])),
+            (1, strings(&["a", "b"])),
+            (2, strings(&["b"])),
+            (3, vec![]),
+        ]
+    );
+}
+
+#[test]
+fn forbid_implicit_coownership() {
+    let app = quote!(
+        mod app {
+            #[shared]
+            struct Shared {
+                a: u32,
+                b: u32,
+            }
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(priority = 2, shared = [a, b])]
+            fn foo(_: foo::Context) {}
+
+            #[task(priority = 2, shared = [a])]
+            fn bar(_: bar::Context) {}
+
+            #[task(priority = 3, shared = [b])]
+            fn baz(_: baz::Context) {}
+        }
+    );
+
+    let (_app, analysis) = crate::parse2(quote!(), app.clone(), Settings::default()).unwrap();
+    assert_eq!(
+        analysis.ownerships[&quote::format_ident!("a")],
+        Ownership::CoOwned { priority: 2 }
+    );
+
+    let err = crate::parse2(
+        quote!(),
+        app,
+        Settings {
+            forbid_implicit_coownership: true,
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+    let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Shared resource \"a\" is co-owned by several tasks at priority 2, which is forbidden",
+            "Shared resource \"a\" is co-owned by several tasks at priority 2, which is forbidden",
+        ]
+    );
+}
+
+#[test]
+fn spawnable_tasks() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(binds = UART0)]
+                fn uart0(_: uart0::Context) {}
+
+                #[task(priority = 2)]
+                fn foo(_: foo::Context) {}
+
+                #[task]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let tasks = analysis
+        .spawnable_tasks
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(tasks, ["bar", "foo"]);
+}
+
+#[test]
+fn task_ceiling() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let ident = |name: &str| quote::format_ident!("{}", name);
+    assert_eq!(analysis.task_ceiling(&ident("foo")), Some(3));
+    assert_eq!(analysis.task_ceiling(&ident("bar")), None);
+    assert_eq!(analysis.task_ceiling(&ident("baz")), None);
+    assert_eq!(analysis.task_ceiling(&ident("init")), None);
+}
+
+#[test]
+fn local_resource_owners() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {
+                    a: u32,
+                    b: u32,
+                    dead: u32,
+                }
+
+                #[init(local = [c: u32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(local = [a])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(local = [b, c: u32 = 0])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let owners = analysis
+        .local_resource_owners
+        .iter()
+        .map(|(name, task)| (name.to_string(), task.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        owners,
+        [
+            ("a".to_string(), "idle".to_string()),
+            ("b".to_string(), "foo".to_string())
+        ]
+    );
+}
+
+#[test]
+fn read_only_after_init() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    dead: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [&a, b])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .read_only_after_init
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(resources, ["a"]);
+}
+
+#[test]
+fn warnings() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {
+                    d: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [c])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let warnings = analysis
+        .warnings
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            "Shared resource \"b\" is never used",
+            "Local resource \"d\" is never used",
+            "Shared resource \"a\" is only used by task \"foo\"; consider making it a local resource",
+        ]
+    );
+}
+
+#[test]
+fn dispatcher_depth() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(capacity = 2)]
+                fn a(_: a::Context) {}
+
+                #[task(capacity = 3)]
+                fn b(_: b::Context) {}
+
+                #[task(priority = 3)]
+                fn c(_: c::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.dispatcher_depth(1), Some(5));
+    assert_eq!(analysis.dispatcher_depth(2), None);
+    assert_eq!(analysis.dispatcher_depth(3), Some(1));
+    assert_eq!(crate::analyze::total_capacity(&analysis.channels), 6);
+}
+
+#[test]
+fn lock_free_idle() {
+    // `init` can't access shared resources but `idle`, at priority 0, is part of the check
+    let err = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    x: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [x])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [x])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+
+    // one error on the declaration and one for each of the two uses
+    assert_eq!(err.into_iter().count(), 3);
+}
+
+#[test]
+fn empty_analysis() {
+    let mut analysis = crate::analyze::Analysis::empty();
+    assert!(analysis.tasks.is_empty());
+    assert!(analysis.is_empty());
+    assert_eq!(analysis.max_priority(), 0);
+
+    analysis.ownerships.insert(
+        quote::format_ident!("a"),
+        Ownership::Contended { ceiling: 2 },
+    );
+    assert!(analysis.ownerships[&quote::format_ident!("a")].needs_lock(1));
+
+    // derived from the public fields
+    assert!(analysis.needs_locking());
+    assert_eq!(
+        analysis.ceiling_for(&quote::format_ident!("a")),
+        CeilingInfo::Lock(2)
+    );
+
+    // computed by `analyze::app` only
+    assert_eq!(analysis.max_priority(), 0);
+    assert!(analysis.all_priorities().is_empty());
+}
+
+#[test]
+fn needs_locking() {
+    let parse = |priority: u8| {
+        let priority = proc_macro2::Literal::u8_unsuffixed(priority);
+        crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {
+                        a: u32,
+                    }
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[task(shared = [a])]
+                    fn foo(_: foo::Context) {}
+
+                    #[task(priority = #priority, shared = [a])]
+                    fn bar(_: bar::Context) {}
+                }
+            ),
+            Settings::default(),
+        )
+        .unwrap()
+        .1
+    };
+
+    assert!(!parse(1).needs_locking());
+    assert!(parse(2).needs_locking());
+}
+
+#[test]
+fn idle_locks() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [a, b])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [a])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .idle_locks
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(resources, ["a"]);
+}
+
+#[test]
+fn inversion_warnings() {
+    let app = quote!(
+        mod app {
+            #[shared]
+            struct Shared {
+                a: u32,
+                b: u32,
+            }
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(shared = [a, b])]
+            fn foo(_: foo::Context) {}
+
+            #[task(priority = 2, shared = [a])]
+            fn bar(_: bar::Context) {}
+
+            #[task(priority = 4, shared = [b])]
+            fn baz(_: baz::Context) {}
+        }
+    );
+
+    let (_app, analysis) = crate::parse2(quote!(), app.clone(), Settings::default()).unwrap();
+    assert!(analysis.inversion_warnings.is_empty());
+
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        app,
+        Settings {
+            priority_inversion_threshold: Some(1),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+    let inversions = analysis
+        .inversion_warnings
+        .iter()
+        .map(|(name, inversion)| (name.to_string(), *inversion))
+        .collect::<Vec<_>>();
+    assert_eq!(inversions, [("b".to_string(), 3)]);
+    assert_eq!(
+        analysis.warnings.last().unwrap().to_string(),
+        "Shared resource \"b\" has a ceiling of 4, 3 levels above the lowest priority that uses it"
+    );
+}
+
+#[test]
+fn total_capacity() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(capacity = 200)]
+                fn a(_: a::Context) {}
+
+                #[task(priority = 2, capacity = 200)]
+                fn b(_: b::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    // more than fits in a `u8`
+    assert_eq!(crate::analyze::total_capacity(&analysis.channels), 400);
+    assert_eq!(analysis.total_spawn_slots(), 400);
+}
+
+#[test]
+fn send_and_sync_types() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // shared across priorities
+                    a: A,
+                    // owned by a single, nonzero priority, task
+                    b: B,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [&a])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.send_types.len(), 2);
+    let types = analysis
+        .send_and_sync_types()
+        .into_iter()
+        .map(|ty| quote!(#ty).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["A"]);
+}
+
+#[test]
+fn all_priorities() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 3)]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 3)]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 5)]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        analysis.all_priorities().into_iter().collect::<Vec<_>>(),
+        [0, 3, 5]
+    );
+}
+
+#[test]
+fn ceiling_for() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    dead: u32,
+                }
+
+                #[local]
+                struct Local {
+                    c: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b], local = [c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let ident = |name: &str| quote::format_ident!("{}", name);
+    assert_eq!(analysis.ceiling_for(&ident("a")), CeilingInfo::Lock(2));
+    assert_eq!(analysis.ceiling_for(&ident("b")), CeilingInfo::NoLock);
+    assert_eq!(analysis.ceiling_for(&ident("c")), CeilingInfo::Unknown);
+    assert_eq!(analysis.ceiling_for(&ident("dead")), CeilingInfo::Unknown);
+}
+
+#[test]
+fn resource_docs() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    /// The first resource
+                    ///
+                    /// It's shared
+                    a: u32,
+                    #[cfg(debug_assertions)]
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {
+                    #[doc = "The second resource"]
+                    c: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let docs = analysis
+        .resource_docs
+        .iter()
+        .map(|(name, docs)| (name.to_string(), docs.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        docs,
+        [
+            (
+                "a".to_string(),
+                vec![
+                    " The first resource".to_string(),
+                    "".to_string(),
+                    " It's shared".to_string()
+                ]
+            ),
+            ("c".to_string(), vec!["The second resource".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn channel_input_types() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context, _: u32, _: [u8; 4]) {}
+
+                #[task]
+                fn bar(_: bar::Context, _: (u8, i16)) {}
+
+                #[task(priority = 2)]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let types = |priority| {
+        analysis.channels[&priority]
+            .input_types
+            .iter()
+            .map(|ty| quote!(#ty).to_string())
+            .collect::<Vec<_>>()
+    };
+    // `bar` comes before `foo`
+    assert_eq!(types(1), ["(u8 , i16)", "u32", "[u8 ; 4]"]);
+    assert!(types(2).is_empty());
+}
+
+#[test]
+fn analysis_error_kinds() {
+    use crate::analyze::AnalysisError;
+
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {
+                    b: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3, shared = [a], local = [b])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let settings = Settings {
+        max_priority: Some(2),
+        ..Settings::default()
+    };
+    match crate::analyze::app(&app, &settings) {
+        Err(AnalysisError::PriorityTooHigh(e)) => assert_eq!(
+            e.to_string(),
+            "Task \"foo\" has priority 3, which exceeds the maximum priority of 2"
+        ),
+        _ => panic!("expected a single `PriorityTooHigh` error"),
+    }
+
+    // the resources are accessed by a single task so this isn't an error
+    let settings = Settings {
+        forbid_implicit_coownership: true,
+        error_on_dead_resources: true,
+        ..Settings::default()
+    };
+    assert!(crate::analyze::app(&app, &settings).is_ok());
+
+    let settings = Settings {
+        max_priority: Some(2),
+        error_on_dead_resources: true,
+        ..Settings::default()
+    };
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3)]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+    match crate::analyze::app(&app, &settings) {
+        Err(AnalysisError::Multiple(errors)) => {
+            assert!(matches!(
+                &errors[..],
+                [
+                    AnalysisError::PriorityTooHigh(_),
+                    AnalysisError::DeadResource(_)
+                ]
+            ));
+        }
+        _ => panic!("expected multiple errors"),
+    }
+
+    let error = crate::analyze::app(&app, &settings).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Task \"foo\" has priority 3, which exceeds the maximum priority of 2\n\
+         Shared resource \"a\" is never used"
+    );
+    let _: &dyn std::error::Error = &error;
+
+    let err: syn::Error = error.into();
+    assert_eq!(err.into_iter().count(), 2);
+}
+
+#[test]
+fn lock_order() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // ceiling 2
+                    a: u32,
+                    // ceiling 4
+                    b: u32,
+                    // ceiling 2
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [c, a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a, c])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 4, shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let locks = analysis.locks[&quote::format_ident!("foo")]
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(locks, ["b", "a", "c"]);
+}
+
+#[test]
+fn location_core() {
+    assert_eq!(Location::Owned { core: None }.core(), None);
+    assert_eq!(Location::Owned { core: Some(1) }.core(), Some(1));
+    assert_eq!(
+        Location::Shared {
+            cores: vec![0, 1].into_iter().collect()
+        }
+        .core(),
+        None
+    );
+
+    // can be used as a key
+    let locations = vec![
+        Location::Owned { core: None },
+        Location::Owned { core: None },
+    ]
+    .into_iter()
+    .collect::<std::collections::HashSet<_>>();
+    assert_eq!(locations.len(), 1);
+}
+
+#[test]
+fn large_inputs() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context, _: [u8; 16], _: [[u32; 4]; 8], _: (u8, [u16; 15])) {}
+
+                #[task]
+                fn bar(_: bar::Context, _: BigStruct, _: &'static [u8; 1024]) {}
+            }
+        ),
+        Settings {
+            large_input_threshold: Some(16),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let inputs = analysis
+        .large_inputs
+        .iter()
+        .map(|(task, ty)| (task.to_string(), quote!(#ty).to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(inputs, [("foo".to_string(), "[[u32 ; 4] ; 8]".to_string())]);
+}
+
+#[test]
+fn large_inputs_saturate() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(
+                    _: foo::Context,
+                    _: ([u8; 18446744073709551615], [u8; 18446744073709551615]),
+                    _: [[u8; 18446744073709551615]; 2],
+                ) {
+                }
+            }
+        ),
+        Settings {
+            large_input_threshold: Some(16),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    // the element counts overflow a `usize`, that must not panic
+    assert_eq!(analysis.large_inputs.len(), 2);
+}
+
+#[test]
+fn single_priority_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // co-owned
+                    a: u32,
+                    // owned by idle
+                    b: u32,
+                    // contended
+                    c: u32,
+                    // owned
+                    d: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [b])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 2, shared = [a, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [c, d])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .single_priority_resources
+        .iter()
+        .map(|(name, priority)| (name.to_string(), *priority))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        resources,
+        [
+            ("b".to_string(), 0),
+            ("a".to_string(), 2),
+            ("d".to_string(), 3)
+        ]
+    );
+}
+
+#[test]
+fn logical_to_hw_priority() {
+    use crate::analyze::logical_to_hw_priority;
+
+    // 3 priority bits
+    assert_eq!(logical_to_hw_priority(1, 3), Some(0xe0));
+    assert_eq!(logical_to_hw_priority(2, 3), Some(0xc0));
+    assert_eq!(logical_to_hw_priority(8, 3), Some(0x00));
+    assert_eq!(logical_to_hw_priority(9, 3), None);
+
+    // 4 priority bits
+    assert_eq!(logical_to_hw_priority(1, 4), Some(0xf0));
+    assert_eq!(logical_to_hw_priority(3, 4), Some(0xd0));
+    assert_eq!(logical_to_hw_priority(16, 4), Some(0x00));
+    assert_eq!(logical_to_hw_priority(17, 4), None);
+
+    assert_eq!(logical_to_hw_priority(0, 4), None);
+    assert_eq!(logical_to_hw_priority(1, 0), None);
+    assert_eq!(logical_to_hw_priority(1, 8), Some(0xff));
+}
+
+#[test]
+fn interrupt_bindings() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = USART1)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = USART2, priority = 2)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let bindings = analysis
+        .interrupt_bindings
+        .iter()
+        .map(|(interrupt, task)| (interrupt.to_string(), task.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bindings,
+        [
+            ("USART1".to_string(), "foo".to_string()),
+            ("USART2".to_string(), "bar".to_string())
+        ]
+    );
+
+    // binding the same interrupt twice is a parse error
+    assert!(crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = USART1)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = USART1)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn shared_resources_in_decl_order() {
+    let (app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    c: u32,
+                    d: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [d, b])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [a, b])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let locations = analysis
+        .shared_resource_locations
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(locations, ["d", "b", "a"]);
+
+    let declared = app
+        .shared_resources_in_decl_order()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(declared, ["a", "b", "d"]);
+}
+
+#[test]
+fn entry_ceilings() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART1, priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(binds = UART2, priority = 3, shared = [b])]
+                fn baz(_: baz::Context) {}
+
+                #[task(binds = UART3, priority = 3)]
+                fn quux(_: quux::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let entry_ceilings = analysis
+        .entry_ceilings
+        .iter()
+        .map(|(task, ceiling)| (task.to_string(), *ceiling))
+        .collect::<Vec<_>>();
+    assert_eq!(entry_ceilings, [("foo".to_string(), 3)]);
+}
+
+#[test]
+fn self_spawn_warning() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context) {
+                    foo::spawn().ok();
+                }
+
+                #[task(capacity = 2)]
+                fn bar(_: bar::Context) {
+                    bar::spawn().ok();
+                }
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.warnings.len(), 1);
+    assert_eq!(
+        analysis.warnings[0].to_string(),
+        "task \"foo\" spawns itself but has a capacity of 1; its message queue may be full when it does"
+    );
+}
+
+#[test]
+fn resource_kind() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    dead: u32,
+                }
+
+                #[local]
+                struct Local {
+                    b: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a], local = [b, c: u32 = 0])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let kind = |name: &str| analysis.resource_kind(&quote::format_ident!("{}", name));
+    assert_eq!(kind("a"), Some(ResourceKind::Shared));
+    assert_eq!(kind("b"), Some(ResourceKind::Local));
+    assert_eq!(kind("c"), Some(ResourceKind::Local));
+    assert_eq!(kind("dead"), None);
+    assert_eq!(kind("unknown"), None);
+}
+
+#[test]
+fn contenders() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3, shared = [a])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [a, b])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let contenders = |name: &str| {
+        analysis
+            .contenders(&quote::format_ident!("{}", name))
+            .into_iter()
+            .map(|(task, priority)| (task.to_string(), priority))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        contenders("a"),
+        [
+            ("bar".to_string(), 1),
+            ("baz".to_string(), 2),
+            ("foo".to_string(), 3)
+        ]
+    );
+    // owned
+    assert!(contenders("b").is_empty());
+    assert!(contenders("unknown").is_empty());
+}
+
+#[test]
+fn contenders_of_built_analysis() {
+    let resource = quote::format_ident!("a");
+    let mut analysis = crate::analyze::Analysis::empty();
+    analysis
+        .ownerships
+        .insert(resource.clone(), Ownership::Contended { ceiling: 2 });
+
+    assert!(analysis.contenders(&resource).is_empty());
+}
+
+#[test]
+fn default_capacity() {
+    let parse = |default_capacity| {
+        crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {}
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[task]
+                    fn foo(_: foo::Context) {}
+
+                    #[task(priority = 1)]
+                    fn bar(_: bar::Context) {}
+
+                    #[task(capacity = 2)]
+                    fn baz(_: baz::Context) {}
+                }
+            ),
+            Settings {
+                default_capacity,
+                ..Settings::default()
+            },
+        )
+    };
+
+    let capacities = |default_capacity| {
+        let (app, analysis) = parse(default_capacity).unwrap();
+        let capacities = app
+            .software_tasks
+            .iter()
+            .map(|(name, task)| (name.to_string(), task.args.capacity))
+            .collect::<Vec<_>>();
+        (capacities, analysis.channels[&1].capacity)
+    };
+
+    let tasks = |foo, bar| {
+        vec![
+            ("foo".to_string(), foo),
+            ("bar".to_string(), bar),
+            ("baz".to_string(), 2),
+        ]
+    };
+    assert_eq!(capacities(None), (tasks(1, 1), 4));
+    assert_eq!(capacities(Some(4)), (tasks(4, 4), 10));
+
+    let error = parse(Some(0)).err().unwrap();
+    let messages = error.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "task \"foo\" has a capacity of 0; specify its `capacity` or use a non-zero default capacity",
+            "task \"bar\" has a capacity of 0; specify its `capacity` or use a non-zero default capacity",
+        ]
+    );
+}
+
+#[test]
+fn mask_levels() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [a])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [a, b, c])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 3, shared = [a, b])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 4, shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let mask_levels = analysis
+        .mask_levels
+        .iter()
+        .map(|(name, levels)| (name.to_string(), levels.iter().cloned().collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        mask_levels,
+        [("a".to_string(), vec![1, 3]), ("b".to_string(), vec![3, 4]),]
+    );
+}
+
+#[test]
+fn validate_priorities() {
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, priority = 5)]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2)]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 4)]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert!(analyze::validate_priorities(&app, &Settings::default()).is_ok());
+
+    let settings = Settings {
+        max_priority: Some(3),
+        ..Settings::default()
+    };
+    let messages = analyze::validate_priorities(&app, &settings)
+        .unwrap_err()
+        .into_iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Task \"foo\" has priority 5, which exceeds the maximum priority of 3",
+            "Task \"baz\" has priority 4, which exceeds the maximum priority of 3",
+        ]
+    );
+}
+
+#[test]
+fn fingerprint() {
+    let fingerprint = |priority: u8| {
+        let priority = proc_macro2::Literal::u8_unsuffixed(priority);
+        let (_app, analysis) = crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {
+                        a: u32,
+                    }
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[task(shared = [a])]
+                    fn foo(_: foo::Context, _: u32) {}
+
+                    #[task(priority = #priority, shared = [a])]
+                    fn bar(_: bar::Context) {}
+                }
+            ),
+            Settings::default(),
+        )
+        .unwrap();
+
+        analysis.fingerprint()
+    };
+
+    assert_eq!(fingerprint(1), fingerprint(1));
+    assert_eq!(fingerprint(2), fingerprint(2));
+    assert_ne!(fingerprint(1), fingerprint(2));
+
+    // the fingerprint only depends on the tokens, so it must not change across runs or compilers
+    assert_eq!(fingerprint(1), 16783702442989837272);
+}
+
+#[test]
+fn resource_mutability() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [&a, b])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let mutability = analysis
+        .resource_mutability
+        .iter()
+        .map(|(name, mutability)| (name.to_string(), *mutability))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        mutability,
+        [
+            ("a".to_string(), Mutability::Shared),
+            ("b".to_string(), Mutability::Exclusive)
+        ]
+    );
+}
+
+#[test]
+fn init_resources_moved_into_tasks_are_send() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: A,
+                    b: B,
+                }
+
+                #[local]
+                struct Local {
+                    c: C,
+                    d: D,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(shared = [b], local = [d])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 3, shared = [a], local = [c])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let send_types = analysis
+        .send_types
+        .iter()
+        .map(|ty| quote!(#ty).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(send_types, ["A", "C"]);
+}
+
+#[test]
+fn dispatcher_summaries() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 2, capacity = 3)]
+                fn foo(_: foo::Context) {}
+
+                #[task]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2)]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let summaries = analysis
+        .dispatcher_summaries()
+        .into_iter()
+        .map(|summary| {
+            (
+                summary.priority,
+                summary.capacity,
+                summary
+                    .tasks
+                    .iter()
+                    .map(|task| task.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summaries,
+        [
+            (1, 1, vec!["bar".to_string()]),
+            (2, 4, vec!["baz".to_string(), "foo".to_string()])
+        ]
+    );
+}
+
+#[test]
+fn local_claimed_by_several_tasks() {
+    let error = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {
+                    a: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(local = [a])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, local = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, local = [a])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+
+    let messages = error.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Local resource \"a\" is claimed by both `foo` (priority 1) and `bar` (priority 2)",
+            "Local resource \"a\" is claimed by both `foo` (priority 1) and `bar` (priority 2)",
+            "Local resource \"a\" is claimed by both `foo` (priority 1) and `baz` (priority 3)",
+        ]
+    );
+}
+
+#[test]
+fn suggest_lock_free() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    #[lock_free]
+                    c: u32,
+                    d: u32,
+                    dead: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b, c, d])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [b, c])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 2, shared = [d])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let suggestions = analysis
+        .suggest_lock_free
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(suggestions, ["a", "b"]);
+}
+
+#[cfg(feature = "source-order")]
+#[test]
+fn errors_in_source_order() {
+    // `quote!` gives every token the same span so the input is parsed from a string instead
+    let input = r#"
+        mod app {
+            #[shared]
+            struct Shared {
+                a: u32,
+            }
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(priority = 2)]
+            fn foo(_: foo::Context) {}
+        }
+    "#;
+
+    // the priorities are checked before looking for dead resources, but `a` comes first
+    let error = crate::parse2(
+        quote!(),
+        input.parse().unwrap(),
+        Settings {
+            max_priority: Some(1),
+            error_on_dead_resources: true,
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+
+    let errors = error
+        .into_iter()
+        .map(|e| (e.span().start().line, e.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            (5, "Shared resource \"a\" is never used".to_string()),
+            (
+                15,
+                "Task \"foo\" has priority 2, which exceeds the maximum priority of 1".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn active_cfgs() {
+    let parse = |active_cfgs: Option<&[&str]>| {
+        let (app, analysis) = crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {
+                        a: u32,
+                        #[cfg(not(feature = "bar"))]
+                        b: u32,
+                    }
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[task(shared = [a])]
+                    fn foo(_: foo::Context) {}
+
+                    #[cfg(all(feature = "bar", not(test)))]
+                    #[task(priority = 2, shared = [a])]
+                    fn bar(_: bar::Context) {}
+
+                    #[cfg(any(test, feature = "baz"))]
+                    #[task(shared = [b])]
+                    fn baz(_: baz::Context) {}
+                }
+            ),
+            Settings {
+                active_cfgs: active_cfgs
+                    .map(|cfgs| cfgs.iter().map(|cfg| cfg.to_string()).collect()),
+                ..Settings::default()
+            },
+        )
+        .unwrap();
+
+        let tasks = app
+            .software_tasks
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        (
+            tasks,
+            analysis.ownerships.get(&quote::format_ident!("a")).cloned(),
+        )
+    };
+
+    let strings = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // without filtering every task is analyzed
+    assert_eq!(
+        parse(None),
+        (
+            strings(&["foo", "bar", "baz"]),
+            Some(Ownership::Contended { ceiling: 2 })
+        )
+    );
+
+    assert_eq!(
+        parse(Some(&[])),
+        (strings(&["foo"]), Some(Ownership::Owned { priority: 1 }))
+    );
+
+    assert_eq!(
+        parse(Some(&["feature = \"bar\""])),
+        (
+            strings(&["foo", "bar"]),
+            Some(Ownership::Contended { ceiling: 2 })
+        )
+    );
+
+    assert_eq!(
+        parse(Some(&["test", "feature=\"baz\""])),
+        (
+            strings(&["foo", "baz"]),
+            Some(Ownership::Owned { priority: 1 })
+        )
+    );
+
+    // predicates that can't be evaluated keep the item, even under `not`
+    let (app, _analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[cfg(not(target_pointer_width = 64))]
+                #[task]
+                fn foo(_: foo::Context) {}
+
+                #[cfg(any(unknown::path, test))]
+                #[task]
+                fn bar(_: bar::Context) {}
+
+                #[cfg(all(unknown::path, test))]
+                #[task]
+                fn baz(_: baz::Context) {}
+
+                #[cfg(feature = "a b")]
+                #[task]
+                fn qux(_: qux::Context) {}
+            }
+        ),
+        Settings {
+            active_cfgs: Some(vec![" feature =  \"a b\" ".to_string()]),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        app.software_tasks
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>(),
+        ["foo", "bar", "qux"]
+    );
+
+    // `baz` accesses `b`, which doesn't exist with feature `bar`
+    let error = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[cfg(not(feature = "bar"))]
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings {
+            active_cfgs: Some(vec!["feature=\"bar\"".to_string()]),
+            ..Settings::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "this shared resource has NOT been declared"
+    );
+}
+
+#[test]
+fn task_priority() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(priority = 2)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART0, priority = 3)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let priority = |name: &str| analysis.task_priority(&quote::format_ident!("{}", name));
+    assert_eq!(priority("init"), Some(0));
+    assert_eq!(priority("idle"), Some(0));
+    assert_eq!(priority("foo"), Some(2));
+    assert_eq!(priority("bar"), Some(3));
+    assert_eq!(priority("baz"), None);
+}
+
+#[test]
+fn non_static_inputs() {
+    let parse = |ty: TokenStream2| {
+        crate::parse2(
+            quote!(),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {}
+
+                    #[local]
+                    struct Local {}
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[task]
+                    fn foo(_: foo::Context, _: #ty) {}
+                }
+            ),
+            Settings::default(),
+        )
+        .err()
+        .map(|error| error.to_string())
+    };
+
+    let error = Some(
+        "software task inputs are stored in a message queue so they can't be bound to a non-`'static` lifetime"
+            .to_string(),
+    );
+    assert_eq!(parse(quote!(&'a u8)), error);
+    assert_eq!(parse(quote!(&u8)), error);
+    assert_eq!(parse(quote!((u32, Option<&'static mut &'a u8>))), error);
+    assert_eq!(parse(quote!(Foo<'a>)), error);
+    assert_eq!(parse(quote!(Box<dyn Send + 'a>)), error);
+    assert_eq!(parse(quote!(Box<dyn Fn(&'a u8)>)), error);
+    assert_eq!(parse(quote!(Box<dyn Iterator<Item = &'a u8>>)), error);
+    assert_eq!(parse(quote!(fn() -> &'a u8)), error);
+
+    assert_eq!(parse(quote!(&'static u8)), None);
+    assert_eq!(parse(quote!([u8; 4])), None);
+    assert_eq!(parse(quote!(Foo<'static, u8>)), None);
+    assert_eq!(parse(quote!(fn(&u8))), None);
+    assert_eq!(parse(quote!(Box<dyn Fn(&u8)>)), None);
+    assert_eq!(parse(quote!(Box<dyn Send + 'static>)), None);
+    assert_eq!(parse(quote!(Box<dyn for<'b> Fn(&'b u8) -> &'b u8>)), None);
+    assert_eq!(parse(quote!(for<'b> fn(&'b u8))), None);
+}
+
+#[test]
+fn init_only_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    // only initialized by `init`
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init(local = [c: u32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [b])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    // `init` can't access shared resources so the ones that no task uses are dead rather than
+    // located
+    let shared = analysis
+        .shared_resource_locations
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(shared, ["b"]);
+
+    // the locals declared in `init` are `&'static mut` references so they need a persistent slot
+    // like any other local resource
+    assert_eq!(
+        analysis.local_resource_locations[&quote::format_ident!("c")],
+        Location::Owned { core: None }
+    );
+}
+
+#[test]
+fn single_task_resources() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[single_task]
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [b])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .single_task_resources
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(resources, ["a"]);
+    assert_eq!(
+        analysis.ownerships[&quote::format_ident!("a")],
+        Ownership::Owned { priority: 1 }
+    );
+}
+
+#[test]
+fn unused_single_task_resource() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[single_task]
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    // only the dead resource check reports it
+    let warnings = analysis
+        .warnings
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, ["Shared resource \"a\" is never used"]);
+    assert!(analysis.single_task_resources.is_empty());
+}
+
+#[test]
+fn priority_gaps() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2)]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 5)]
+                fn baz(_: baz::Context) {}
+
+                // not a dispatcher
+                #[task(binds = UART0, priority = 7)]
+                fn quux(_: quux::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(analysis.priority_gaps, [3, 4]);
+}
+
+#[test]
+fn resources_by_core() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    dead: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [b, a])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let resources = analysis
+        .resources_by_core()
+        .into_iter()
+        .map(|(core, names)| {
+            (
+                core,
+                names
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(resources, [(None, vec!["b".to_string(), "a".to_string()])]);
+}
+
+#[test]
+fn diff() {
+    let (_app, old) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let (_app, new) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(capacity = 2, shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [b])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert!(old.diff(&old).is_empty());
+
+    let diff = old.diff(&new);
+    let ident = |name: &str| quote::format_ident!("{}", name);
+    assert_eq!(diff.added_tasks, [ident("baz")]);
+    assert_eq!(diff.removed_tasks, [ident("bar")]);
+    assert_eq!(
+        diff.ownerships,
+        [
+            (
+                ident("a"),
+                Some(Ownership::Contended { ceiling: 2 }),
+                Some(Ownership::Owned { priority: 1 })
+            ),
+            (
+                ident("b"),
+                Some(Ownership::Owned { priority: 1 }),
+                Some(Ownership::Contended { ceiling: 2 })
+            ),
+        ]
+    );
+    assert_eq!(diff.capacities, [(1, Some(1), Some(2))]);
+}
+
+#[test]
+fn global_locks() {
+    let (_app, analysis) = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                    c: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [a, b])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [a])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [b, c])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(analysis.max_priority(), 3);
+
+    // `c` is only used at priority 3, so it's never locked
+    let global_locks = analysis
+        .global_locks
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(global_locks, ["b"]);
+}
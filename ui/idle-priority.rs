@@ -0,0 +1,9 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[idle(priority = 1)]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+}
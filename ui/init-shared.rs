@@ -0,0 +1,15 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        a: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init(shared = [a])]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+}
@@ -0,0 +1,7 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[monotonic(binds = Tim1, shared = [a])]
+    type Fast = hal::Tim1Monotonic;
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        a: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [a as b])]
+    fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,20 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        a: u32,
+    }
+
+    #[local]
+    struct Local {
+        a: u32,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [a])]
+    fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,25 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        e1: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(priority = 1, shared = [e1])]
+    fn foo(_: foo::Context) {}
+
+    #[task(priority = 1, shared = [e1])]
+    fn bar(_: bar::Context) {}
+
+    #[task(priority = 2, shared = [e1])]
+    fn baz(_: baz::Context) {}
+}
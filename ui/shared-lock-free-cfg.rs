@@ -0,0 +1,19 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        #[cfg(feature = "a")]
+        #[lock_free]
+        e1: u32,
+        #[cfg(not(feature = "a"))]
+        e1: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        #[single_task]
+        a: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [a])]
+    fn foo(_: foo::Context) {}
+
+    #[task(shared = [a])]
+    fn bar(_: bar::Context) {}
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+#[mock::app(parse_binds)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(binds = UART0, capacity = 2)]
+    fn foo(_: foo::Context) {}
+}
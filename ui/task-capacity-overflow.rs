@@ -0,0 +1,22 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(capacity = 200)]
+    fn foo(_: foo::Context) {}
+
+    #[task(capacity = 100)]
+    fn bar(_: bar::Context) {}
+
+    #[task(priority = 2, capacity = 200)]
+    fn baz(_: baz::Context) {}
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task]
+    fn foo(_: foo::Context, a: Box<dyn Send + 'a>, b: Box<dyn Send + 'static>) {}
+}
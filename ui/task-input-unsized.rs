@@ -0,0 +1,16 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task]
+    fn foo(_: foo::Context, a: impl Copy, b: dyn Send, c: [u8], d: &'static [u8], e: Box<dyn Send>) {}
+}